@@ -0,0 +1,7 @@
+-- Position n of the factorial number system weighs (n + 1)! and takes the
+-- digits 0..n + 1.
+local weights = require 'weights'
+
+return function (n)
+    return { weight = weights.factorial(n + 1), max_digit = n + 1 }
+end
@@ -0,0 +1,14 @@
+-- Weight sequences shared by the sample configs.
+local weights = {}
+
+local factorials = { [0] = bigint.new(1) }
+
+-- n! for n >= 0, remembering every factorial computed so far.
+function weights.factorial(n)
+    for k = #factorials + 1, n do
+        factorials[k] = factorials[k - 1] * k
+    end
+    return factorials[n]
+end
+
+return weights
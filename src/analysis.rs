@@ -0,0 +1,379 @@
+use crate::error::{ArithmeticError, Result};
+use crate::system::{feasible_digits, places_within, sum_ranges, NumeralSystem, Place};
+use num_bigint::BigInt;
+use num_traits::{Signed, Zero};
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+};
+
+/// How many counterexamples of each sort are kept.
+const MAX_COUNTEREXAMPLES: usize = 10;
+
+/// How well a system writes the numbers from 0 up to a bound: whether each has
+/// a representation, and only one. Representations are digit values, most
+/// significant first, over the analysed positions without leading zeros.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Analysis {
+    pub bound: u64,
+    /// The positions analysed, from the least significant one up.
+    pub places: Vec<Place>,
+    /// The first position whose weight is not larger in magnitude than the
+    /// weight below it.
+    pub not_growing: Option<usize>,
+    /// Where the numbers the lower positions reach fall short of or overlap
+    /// the next weight, for systems with positive weights and digits from 0.
+    pub coverage: Option<Vec<Coverage>>,
+    /// Numbers without a representation, up to [`MAX_COUNTEREXAMPLES`].
+    pub missing: Vec<BigInt>,
+    pub missing_count: u64,
+    /// Numbers with several representations, up to [`MAX_COUNTEREXAMPLES`].
+    pub ambiguous: Vec<Ambiguity>,
+    pub ambiguous_count: u64,
+}
+
+/// How the digits of the positions below `position` meet its weight. They
+/// reach every number up to `reachable`, so `weight` should be one more.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Coverage {
+    Gap {
+        position: usize,
+        reachable: BigInt,
+        weight: BigInt,
+    },
+    Overlap {
+        position: usize,
+        reachable: BigInt,
+        weight: BigInt,
+    },
+}
+
+/// A number and two of its representations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ambiguity {
+    pub number: BigInt,
+    pub representations: [Vec<isize>; 2],
+}
+
+/// The representations found so far for a partial sum, capped at two.
+#[derive(Debug, Clone, Default)]
+struct Found {
+    count: u8,
+    examples: Vec<Vec<isize>>,
+}
+
+impl Analysis {
+    /// Whether every number up to the bound has a representation.
+    pub fn is_complete(&self) -> bool {
+        self.missing_count == 0
+    }
+
+    /// Whether no number up to the bound has more than one representation.
+    pub fn is_unique(&self) -> bool {
+        self.ambiguous_count == 0
+    }
+}
+
+/// Analyses how `system` writes the numbers from 0 to `bound`, over the
+/// positions [`places_within`] the bound.
+pub fn analyze(system: &dyn NumeralSystem, bound: u64) -> Result<Analysis> {
+    let places = places_within(&BigInt::from(bound), |position| system.place_at(position))?;
+    if places.is_empty() {
+        return Err(ArithmeticError::NotPositional.into());
+    }
+    let found = count_representations(&places, &BigInt::from(bound));
+
+    let mut analysis = Analysis {
+        bound,
+        not_growing: (1..places.len())
+            .find(|&k| places[k].weight.magnitude() <= places[k - 1].weight.magnitude()),
+        coverage: coverage(&places),
+        places,
+        missing: Vec::new(),
+        missing_count: 0,
+        ambiguous: Vec::new(),
+        ambiguous_count: 0,
+    };
+    for number in 0..=bound {
+        let number = BigInt::from(number);
+        match found.get(&number) {
+            None => {
+                analysis.missing_count += 1;
+                if analysis.missing.len() < MAX_COUNTEREXAMPLES {
+                    analysis.missing.push(number);
+                }
+            }
+            Some(found) if found.count > 1 => {
+                analysis.ambiguous_count += 1;
+                if analysis.ambiguous.len() < MAX_COUNTEREXAMPLES {
+                    let [first, second] = [0, 1].map(|i| strip_zeros(&found.examples[i]));
+                    analysis.ambiguous.push(Ambiguity {
+                        number,
+                        representations: [first, second],
+                    });
+                }
+            }
+            Some(_) => {}
+        }
+    }
+    Ok(analysis)
+}
+
+/// Counts the digit strings over `places` whose values are in `0..=bound`,
+/// from the most significant position down. Partial sums the lower positions
+/// cannot bring back into range are dropped.
+fn count_representations(places: &[Place], bound: &BigInt) -> BTreeMap<BigInt, Found> {
+    // The lowest and highest sums the positions below each position reach.
+    let (low, high) = sum_ranges(places);
+
+    let mut found = BTreeMap::from([(
+        BigInt::zero(),
+        Found {
+            count: 1,
+            examples: vec![Vec::new()],
+        },
+    )]);
+    for (position, place) in places.iter().enumerate().rev() {
+        let mut next: BTreeMap<BigInt, Found> = BTreeMap::new();
+        for (sum, partial) in &found {
+            let digits = feasible_digits(
+                place,
+                &(bound - &low[position] - sum),
+                &(-&high[position] - sum),
+            );
+            let Some((min, max)) = digits else {
+                continue;
+            };
+            // All digits add the same at a zero weight, so two of them tell
+            // that there are several representations.
+            let count = match place.weight.is_zero() {
+                true => 2,
+                false => usize::MAX,
+            };
+            for digit in (min..=max).take(count) {
+                let entry = next.entry(sum + &place.weight * digit).or_default();
+                entry.count = entry.count.saturating_add(partial.count).min(2);
+                for example in &partial.examples {
+                    if entry.examples.len() < 2 {
+                        let mut example = example.clone();
+                        example.push(digit);
+                        entry.examples.push(example);
+                    }
+                }
+            }
+        }
+        found = next;
+    }
+    found
+}
+
+fn strip_zeros(digits: &[isize]) -> Vec<isize> {
+    let first = digits.iter().position(|&d| d != 0).unwrap_or(digits.len());
+    digits[first..].to_vec()
+}
+
+impl Display for Coverage {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Coverage::Gap {
+                position,
+                reachable,
+                weight,
+            } => write!(
+                f,
+                "gap below position {}: lower positions reach {}, but its weight is {}",
+                position, reachable, weight
+            ),
+            Coverage::Overlap {
+                position,
+                reachable,
+                weight,
+            } => write!(
+                f,
+                "overlap below position {}: lower positions reach {}, past its weight {}",
+                position, reachable, weight
+            ),
+        }
+    }
+}
+
+/// Where the numbers reachable with the positions below each position meet
+/// its weight. Only applies to positive weights with digits from 0.
+fn coverage(places: &[Place]) -> Option<Vec<Coverage>> {
+    let applies = places
+        .iter()
+        .all(|place| place.weight.is_positive() && place.min_digit == 0);
+    if !applies {
+        return None;
+    }
+    let mut reachable = BigInt::zero();
+    let mut findings = Vec::new();
+    for (position, place) in places.iter().enumerate() {
+        let weight = place.weight.clone();
+        let expected = &reachable + 1;
+        if weight > expected {
+            findings.push(Coverage::Gap {
+                position,
+                reachable: reachable.clone(),
+                weight,
+            });
+        } else if weight < expected {
+            findings.push(Coverage::Overlap {
+                position,
+                reachable: reachable.clone(),
+                weight,
+            });
+        }
+        reachable += &place.weight * place.max_digit;
+    }
+    Some(findings)
+}
+
+/// Digit values most significant first, separated by `:` as digits may have
+/// several decimal places.
+pub struct Digits<'a>(pub &'a [isize]);
+
+impl Display for Digits<'_> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "0");
+        }
+        let digits: Vec<String> = self.0.iter().map(isize::to_string).collect();
+        write!(f, "{}", digits.join(":"))
+    }
+}
+
+impl Display for Analysis {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let weights: Vec<String> = self.places.iter().map(|p| p.weight.to_string()).collect();
+        writeln!(
+            f,
+            "positions analysed: {} (weights {})",
+            self.places.len(),
+            weights.join(", ")
+        )?;
+        match self.not_growing {
+            None => writeln!(f, "weights grow in magnitude: yes")?,
+            Some(position) => writeln!(
+                f,
+                "weights grow in magnitude: no, not at position {}",
+                position
+            )?,
+        }
+        match &self.coverage {
+            None => writeln!(
+                f,
+                "digit coverage: not checked, as it needs positive weights and digits from 0"
+            )?,
+            Some(findings) if findings.is_empty() => writeln!(f, "digit coverage: exact")?,
+            Some(findings) => {
+                writeln!(f, "digit coverage:")?;
+                for finding in findings {
+                    writeln!(f, "  {}", finding)?;
+                }
+            }
+        }
+        let missing: Vec<String> = self.missing.iter().map(BigInt::to_string).collect();
+        writeln!(
+            f,
+            "numbers 0..={} without a representation: {}{}",
+            self.bound,
+            self.missing_count,
+            match missing.is_empty() {
+                true => String::new(),
+                false => format!(", e.g. {}", missing.join(", ")),
+            }
+        )?;
+        writeln!(
+            f,
+            "numbers 0..={} with several representations: {}",
+            self.bound, self.ambiguous_count
+        )?;
+        for ambiguity in &self.ambiguous {
+            let [first, second] = &ambiguity.representations;
+            writeln!(
+                f,
+                "  {} = {} = {}",
+                ambiguity.number,
+                Digits(first),
+                Digits(second)
+            )?;
+        }
+        let yes_no = |yes: bool| if yes { "yes" } else { "no" };
+        write!(
+            f,
+            "complete: {}, unique: {}",
+            yes_no(self.is_complete()),
+            yes_no(self.is_unique())
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::System;
+    use crate::system::Registry;
+
+    fn analyze_system(system: System, bound: u64) -> Analysis {
+        let system = Registry::default().build(&system).unwrap();
+        analyze(system.as_ref(), bound).unwrap()
+    }
+
+    #[test]
+    fn test_positional_systems_are_exact() {
+        for system in [
+            System::constant(10),
+            System::constant(-2),
+            System::constant(3).with_alphabet([(-1, 'T'), (0, '0'), (1, '1')]),
+            System::expr("2^n", "01"),
+        ] {
+            let analysis = analyze_system(system, 500);
+
+            assert!(analysis.is_complete(), "{}", analysis);
+            assert!(analysis.is_unique(), "{}", analysis);
+        }
+    }
+
+    #[test]
+    fn test_negative_weights_are_incomplete() {
+        let system = System::lua("return function (n) return -bigint.new(10)^n end", "01");
+
+        let analysis = analyze_system(system, 100);
+
+        assert_eq!(analysis.missing_count, 100);
+        assert_eq!(analysis.missing[..3], [1, 2, 3].map(BigInt::from));
+        assert!(analysis.is_unique());
+        assert_eq!(analysis.coverage, None);
+    }
+
+    #[test]
+    fn test_gaps_and_overlaps() {
+        let gappy = System::table([1, 3, 4].map(BigInt::from), "01");
+        let analysis = analyze_system(gappy, 8);
+
+        assert_eq!(analysis.missing, [2, 6].map(BigInt::from));
+        assert_eq!(
+            analysis.ambiguous,
+            vec![Ambiguity {
+                number: BigInt::from(4),
+                representations: [vec![1, 1], vec![1, 0, 0]],
+            }]
+        );
+        assert_eq!(
+            analysis.coverage.unwrap()[0].to_string(),
+            "gap below position 1: lower positions reach 1, but its weight is 3"
+        );
+        assert_eq!(analysis.not_growing, None);
+    }
+
+    #[test]
+    fn test_weights_that_do_not_grow() {
+        let ones = System::expr("1", "01");
+
+        let analysis = analyze_system(ones, 3);
+
+        assert_eq!(analysis.not_growing, Some(1));
+        assert!(!analysis.is_unique());
+    }
+}
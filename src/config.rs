@@ -1,101 +1,319 @@
+mod validation;
+
 use crate::consts::{DECIMAL_ALPHABET, DEFAULT_BASE};
-use serde::Deserialize;
+use crate::error::{ArithmeticError, ConfigError, ParseErrorReason};
+use crate::system::{Decomposition, Registry, Sandbox};
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{ToPrimitive, Zero};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
 use std::{
+    collections::BTreeMap,
     fmt::{self, Display, Formatter},
     fs,
+    path::{Path, PathBuf},
 };
 
-use crate::CommandLineArgs;
+pub use validation::{validate, validate_str, Diagnostic, Problem};
 
-#[derive(Deserialize, Debug, Clone)]
-pub(crate) struct System {
-    kind: String,
+/// A numeral system: how digit values are weighted by position, and which
+/// symbol writes each digit value.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct System {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kind: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     base: Option<isize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     function: Option<String>,
+    /// A lua file with the function, instead of `function`. Relative paths in
+    /// config files are relative to the config file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    function_file: Option<PathBuf>,
+    /// Lua files the function can `require` by their name without extension.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    libraries: Vec<PathBuf>,
+    /// An expression over the position `n` giving its weight, evaluated
+    /// without lua.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expression: Option<String>,
+    /// The weights of a table system, or of the first positions of an
+    /// expression system. Weights too large for JSON numbers are written as
+    /// decimal strings, and fractions as strings such as `"1/6"`.
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        serialize_with = "serialize_weights",
+        deserialize_with = "deserialize_weights"
+    )]
+    weights: Vec<BigRational>,
+    /// A file with the weights of a table system, one per line, instead of
+    /// `weights`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    weights_file: Option<PathBuf>,
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        serialize_with = "serialize_alphabet",
+        deserialize_with = "deserialize_alphabet"
+    )]
     alphabet: Vec<(isize, char)>,
+    /// Alphabets for the positions from 0 up, where they differ from
+    /// `alphabet`. With `cycle` they repeat over all positions.
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        serialize_with = "serialize_alphabets",
+        deserialize_with = "deserialize_alphabets"
+    )]
+    alphabets: Vec<Vec<(isize, char)>>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    cycle: bool,
+    /// Lua functions writing the digits instead of an alphabet.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    symbol_functions: Option<SymbolFunctions>,
+    /// The position the function or expression is evaluated at, or the index
+    /// of the table weight used, for the last digit before the radix point.
+    /// Positions after the point count down from it. Defaults to 0.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    origin: Option<isize>,
+    /// How digits are picked when writing numbers with computed weights.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    decomposition: Option<Decomposition>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sandbox: Option<Sandbox>,
+    /// Lua modules by name, for the function to `require`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    modules: BTreeMap<String, String>,
+    /// Values the lua function sees as the global `params` and as
+    /// `context.params`.
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    params: Map<String, Value>,
+    /// Settings not known to the built-in kinds, for registered kinds to read.
+    #[serde(flatten)]
+    options: Map<String, Value>,
+}
+
+/// Lua functions that write digit values as symbols and read them back, for
+/// bases too large to list every symbol.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SymbolFunctions {
+    /// Lua code returning a function from a digit value to its symbol.
+    pub symbol: String,
+    /// Lua code returning a function from a symbol to its digit value, or nil.
+    pub value: String,
+    /// A lua pattern matching one symbol. Without it every character is a
+    /// symbol.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    pub min_value: isize,
+    pub max_value: isize,
 }
 
-#[derive(Deserialize, Debug, Clone)]
-pub(crate) struct Config {
+/// The pair of systems a number is converted between.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Config {
     source: System,
     target: System,
 }
 
+/// An alphabet entry as it is written in config files.
+#[derive(Serialize, Deserialize)]
+struct Letter {
+    symbol: char,
+    value: isize,
+}
+
+fn serialize_alphabet<S: Serializer>(
+    alphabet: &[(isize, char)],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(
+        alphabet
+            .iter()
+            .map(|&(value, symbol)| Letter { symbol, value }),
+    )
+}
+
+fn deserialize_alphabet<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<(isize, char)>, D::Error> {
+    let letters = Vec::<Letter>::deserialize(deserializer)?;
+    Ok(letters.into_iter().map(|l| (l.value, l.symbol)).collect())
+}
+
+fn serialize_alphabets<S: Serializer>(
+    alphabets: &[Vec<(isize, char)>],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(alphabets.iter().map(|alphabet| {
+        alphabet
+            .iter()
+            .map(|&(value, symbol)| Letter { symbol, value })
+            .collect::<Vec<_>>()
+    }))
+}
+
+fn deserialize_alphabets<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Vec<(isize, char)>>, D::Error> {
+    let alphabets = Vec::<Vec<Letter>>::deserialize(deserializer)?;
+    Ok(alphabets
+        .into_iter()
+        .map(|letters| letters.into_iter().map(|l| (l.value, l.symbol)).collect())
+        .collect())
+}
+
+fn serialize_weights<S: Serializer>(
+    weights: &[BigRational],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(weights.iter().map(|weight| {
+        match weight.is_integer().then(|| weight.to_i64()).flatten() {
+            Some(weight) => Value::from(weight),
+            None => Value::from(weight.to_string()),
+        }
+    }))
+}
+
+fn deserialize_weights<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<BigRational>, D::Error> {
+    Vec::<Value>::deserialize(deserializer)?
+        .iter()
+        .map(|weight| {
+            parse_weight(weight).ok_or_else(|| {
+                D::Error::custom("expected an integer, a decimal string or a fraction")
+            })
+        })
+        .collect()
+}
+
+/// The weight a config value stands for: an integer, or a string with a
+/// decimal integer or a fraction such as `1/6`.
+pub(crate) fn parse_weight(weight: &Value) -> Option<BigRational> {
+    match weight {
+        Value::Number(n) => n
+            .as_i64()
+            .map(BigInt::from)
+            .or_else(|| n.as_u64().map(BigInt::from))
+            .map(BigRational::from),
+        Value::String(s) => parse_weight_str(s),
+        _ => None,
+    }
+}
+
+/// Reads a decimal integer or a fraction such as `-1/6`.
+pub(crate) fn parse_weight_str(weight: &str) -> Option<BigRational> {
+    let (numerator, denominator) = match weight.split_once('/') {
+        Some((numerator, denominator)) => (numerator, denominator.trim().parse().ok()?),
+        None => (weight, BigInt::from(1)),
+    };
+    let numerator: BigInt = numerator.trim().parse().ok()?;
+    (!denominator.is_zero()).then(|| BigRational::new(numerator, denominator))
+}
+
 impl Config {
-    pub fn new(command_line_args: CommandLineArgs) -> Result<Self, &'static str> {
-        if command_line_args.config_file.is_some() {
-            let config_file_path = command_line_args
-                .config_file
-                .expect("Config file path not provided");
-            let config_file =
-                fs::read_to_string(config_file_path).expect("Failed to read config file");
-            let config: Config =
-                serde_json::from_str(&config_file).expect("Failed to parse config file");
+    /// Pairs a source and a target system, checking both as a config file would be.
+    pub fn new(source: System, target: System) -> Result<Self, ConfigError> {
+        Self::new_with(source, target, &Registry::default())
+    }
+
+    /// Like [`Config::new`], allowing the kinds known to `registry`.
+    pub fn new_with(
+        source: System,
+        target: System,
+        registry: &Registry,
+    ) -> Result<Self, ConfigError> {
+        let config = Self { source, target };
+        let diagnostics = config.validate_with(registry);
+        if diagnostics.is_empty() {
             Ok(config)
         } else {
-            let source_base = match command_line_args.source_base {
-                Some(v) => v,
-                None => match &command_line_args.source_alphabet {
-                    Some(alphabet) => alphabet.len() as isize,
-                    None => DEFAULT_BASE,
-                },
-            };
-
-            if source_base < 0 && command_line_args.source_number.starts_with('-') {
-                return Err("Both base and number cannot be negative");
-            }
-
-            let target_base = match command_line_args.target_base {
-                Some(v) => v,
-                None => match &command_line_args.target_alphabet {
-                    Some(alphabet) => alphabet.len() as isize,
-                    None => DEFAULT_BASE,
-                },
-            };
-            let source_alphabet = match &command_line_args.source_alphabet {
-                Some(alphabet) => alphabet
-                    .chars()
-                    .enumerate()
-                    .map(|x| (x.0 as isize, x.1))
-                    .collect(),
-                None => DECIMAL_ALPHABET
-                    .chars()
-                    .enumerate()
-                    .take(source_base as usize)
-                    .map(|x| (x.0 as isize, x.1))
-                    .collect(),
-            };
-
-            let target_alphabet = match &command_line_args.target_alphabet {
-                Some(alphabet) => alphabet
-                    .chars()
-                    .enumerate()
-                    .map(|x| (x.0 as isize, x.1))
-                    .collect(),
-                None => DECIMAL_ALPHABET
-                    .chars()
-                    .enumerate()
-                    .take(target_base as usize)
-                    .map(|x| (x.0 as isize, x.1))
-                    .collect(),
-            };
-            Ok(Self {
-                source: System {
-                    kind: "constant".to_string(),
-                    base: Some(source_base),
-                    function: None,
-                    alphabet: source_alphabet,
-                },
-                target: System {
-                    kind: "constant".to_string(),
-                    base: Some(target_base),
-                    function: None,
-                    alphabet: target_alphabet,
-                },
-            })
+            Err(ConfigError::Invalid(diagnostics))
         }
     }
 
+    /// Reads and validates a JSON config file, reporting every problem found.
+    /// Lua files named in the config are found relative to its directory.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        Self::from_file_with(path, &Registry::default())
+    }
+
+    /// Like [`Config::from_file`], allowing the kinds known to `registry`.
+    pub fn from_file_with(
+        path: impl AsRef<Path>,
+        registry: &Registry,
+    ) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let config_file = fs::read_to_string(path).map_err(|e| {
+            ConfigError::Invalid(vec![Diagnostic {
+                path: "$".to_string(),
+                problem: Problem::Unreadable(e.to_string()),
+            }])
+        })?;
+        let directory = path.parent().unwrap_or(Path::new(""));
+        let config = Self::from_json_with(&config_file, registry)?;
+        Ok(config.map_systems(|system| system.resolve_paths(directory)))
+    }
+
+    /// Parses and validates a JSON config, reporting every problem found.
+    pub fn from_json(json: &str) -> Result<Self, ConfigError> {
+        Self::from_json_with(json, &Registry::default())
+    }
+
+    /// Like [`Config::from_json`], allowing the kinds known to `registry`.
+    pub fn from_json_with(json: &str, registry: &Registry) -> Result<Self, ConfigError> {
+        let config = validate_str(json, &registry.kinds()).map_err(ConfigError::Invalid)?;
+        serde_json::from_value(config).map_err(|e| {
+            ConfigError::Invalid(vec![Diagnostic {
+                path: "$".to_string(),
+                problem: Problem::Unreadable(e.to_string()),
+            }])
+        })
+    }
+
+    /// Runs the same checks as for config files against this config.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        self.validate_with(&Registry::default())
+    }
+
+    /// Like [`Config::validate`], allowing the kinds known to `registry`.
+    pub fn validate_with(&self, registry: &Registry) -> Vec<Diagnostic> {
+        match serde_json::to_value(self) {
+            Ok(config) => validate(&config, &registry.kinds()),
+            Err(e) => vec![Diagnostic {
+                path: "$".to_string(),
+                problem: Problem::Unreadable(e.to_string()),
+            }],
+        }
+    }
+
+    /// Applies `f` to the source and the target system.
+    pub fn map_systems(self, mut f: impl FnMut(System) -> System) -> Self {
+        Self {
+            source: f(self.source),
+            target: f(self.target),
+        }
+    }
+
+    /// Sets a param of the source or target system from an assignment such as
+    /// `source.base=16`. The value is read as JSON, or taken as a string if it
+    /// is not valid JSON.
+    pub fn with_param_assignment(mut self, assignment: &str) -> Result<Self, ConfigError> {
+        let invalid = || ConfigError::InvalidParam(assignment.to_string());
+        let (name, value) = assignment.split_once('=').ok_or_else(invalid)?;
+        let (system, name) = match name.split_once('.') {
+            Some(("source", name)) if !name.is_empty() => (&mut self.source, name),
+            Some(("target", name)) if !name.is_empty() => (&mut self.target, name),
+            _ => return Err(invalid()),
+        };
+        let value = serde_json::from_str(value).unwrap_or_else(|_| Value::from(value));
+        system.params.insert(name.to_string(), value);
+        Ok(self)
+    }
+
     pub fn get_source(&self) -> &System {
         &self.source
     }
@@ -122,32 +340,335 @@ impl Config {
 }
 
 impl System {
+    /// A system of constant `base` written with the first `|base|` symbols of
+    /// [`DECIMAL_ALPHABET`].
+    pub fn constant(base: isize) -> Self {
+        Self::positional(Some(base), None)
+    }
+
+    /// A system whose weights come from a lua `function` returning the weight
+    /// of a position, written with `symbols` valued from 0.
+    pub fn lua(function: impl Into<String>, symbols: &str) -> Self {
+        Self {
+            kind: Some("lua".to_string()),
+            base: None,
+            function: Some(function.into()),
+            function_file: None,
+            libraries: Vec::new(),
+            expression: None,
+            weights: Vec::new(),
+            weights_file: None,
+            alphabet: enumerate_symbols(symbols.chars()),
+            alphabets: Vec::new(),
+            cycle: false,
+            symbol_functions: None,
+            origin: None,
+            decomposition: None,
+            sandbox: None,
+            modules: BTreeMap::new(),
+            params: Map::new(),
+            options: Map::new(),
+        }
+    }
+
+    /// A constant base system as given on the command line: the base defaults
+    /// to the number of `symbols`, and the symbols to the first `|base|` of
+    /// [`DECIMAL_ALPHABET`]. Without either the base is [`DEFAULT_BASE`].
+    pub fn positional(base: Option<isize>, symbols: Option<&str>) -> Self {
+        let base = match (base, symbols) {
+            (Some(base), _) => base,
+            (None, Some(symbols)) => symbols.chars().count() as isize,
+            (None, None) => DEFAULT_BASE,
+        };
+        let alphabet = match symbols {
+            Some(symbols) => enumerate_symbols(symbols.chars()),
+            None => enumerate_symbols(DECIMAL_ALPHABET.chars().take(base.unsigned_abs())),
+        };
+        Self {
+            kind: Some("constant".to_string()),
+            base: Some(base),
+            function: None,
+            function_file: None,
+            libraries: Vec::new(),
+            expression: None,
+            weights: Vec::new(),
+            weights_file: None,
+            alphabet,
+            alphabets: Vec::new(),
+            cycle: false,
+            symbol_functions: None,
+            origin: None,
+            decomposition: None,
+            sandbox: None,
+            modules: BTreeMap::new(),
+            params: Map::new(),
+            options: Map::new(),
+        }
+    }
+
+    /// A system whose position `n` has the `n`th of `weights`, written with
+    /// `symbols` valued from 0.
+    pub fn table(weights: impl IntoIterator<Item = BigInt>, symbols: &str) -> Self {
+        Self {
+            kind: Some("table".to_string()),
+            base: None,
+            weights: weights.into_iter().map(BigRational::from).collect(),
+            ..Self::positional(None, Some(symbols))
+        }
+    }
+
+    /// A system whose weights come from an `expression` over the position `n`,
+    /// such as `16^n` or `(n+1)!`, written with `symbols` valued from 0.
+    pub fn expr(expression: impl Into<String>, symbols: &str) -> Self {
+        Self {
+            kind: Some("expr".to_string()),
+            base: None,
+            expression: Some(expression.into()),
+            ..Self::positional(None, Some(symbols))
+        }
+    }
+
+    /// Replaces the alphabet with explicit `(value, symbol)` pairs, e.g. for
+    /// digit values that do not start at 0.
+    pub fn with_alphabet(mut self, alphabet: impl IntoIterator<Item = (isize, char)>) -> Self {
+        self.alphabet = alphabet.into_iter().collect();
+        self
+    }
+
+    /// Sets alphabets for the positions from 0 up. Past the last one positions
+    /// use the main alphabet, unless `cycle` repeats them for every position.
+    pub fn with_alphabets(
+        mut self,
+        alphabets: impl IntoIterator<Item = Vec<(isize, char)>>,
+        cycle: bool,
+    ) -> Self {
+        self.alphabets = alphabets.into_iter().collect();
+        self.cycle = cycle;
+        self
+    }
+
+    /// Writes digits with lua functions instead of an alphabet.
+    pub fn with_symbol_functions(mut self, functions: SymbolFunctions) -> Self {
+        self.symbol_functions = Some(functions);
+        self
+    }
+
+    /// Sets the kind, e.g. to one added to a [`Registry`].
+    pub fn with_kind(mut self, kind: impl Into<String>) -> Self {
+        self.kind = Some(kind.into());
+        self
+    }
+
+    /// Sets the position the weights are evaluated at for the last digit
+    /// before the radix point, or the index of its table weight.
+    pub fn with_origin(mut self, origin: isize) -> Self {
+        self.origin = Some(origin);
+        self
+    }
+
+    /// Sets how digits are picked when writing numbers, for the kinds with
+    /// weights other than powers of a base.
+    pub fn with_decomposition(mut self, decomposition: Decomposition) -> Self {
+        self.decomposition = Some(decomposition);
+        self
+    }
+
+    /// Sets the limits for running the lua function of this system.
+    pub fn with_sandbox(mut self, sandbox: Sandbox) -> Self {
+        self.sandbox = Some(sandbox);
+        self
+    }
+
+    /// Reads the lua function from `path` instead of the `function` string.
+    pub fn with_function_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.function = None;
+        self.function_file = Some(path.into());
+        self
+    }
+
+    /// Adds a lua file the function can `require` by its name without extension.
+    pub fn with_library(mut self, path: impl Into<PathBuf>) -> Self {
+        self.libraries.push(path.into());
+        self
+    }
+
+    /// Lists the weights of a table system, or of the first positions of an
+    /// expression system. Table weights before the origin may be fractions.
+    pub fn with_weights<W: Into<BigRational>>(
+        mut self,
+        weights: impl IntoIterator<Item = W>,
+    ) -> Self {
+        self.weights = weights.into_iter().map(Into::into).collect();
+        self.weights_file = None;
+        self
+    }
+
+    /// Reads the weights of a table system from `path` instead of `weights`.
+    pub fn with_weights_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.weights.clear();
+        self.weights_file = Some(path.into());
+        self
+    }
+
+    /// Makes relative file paths relative to `directory`.
+    fn resolve_paths(mut self, directory: &Path) -> Self {
+        let resolve = |path: PathBuf| match path.is_relative() {
+            true => directory.join(path),
+            false => path,
+        };
+        self.function_file = self.function_file.map(resolve);
+        self.libraries = self.libraries.into_iter().map(resolve).collect();
+        self.weights_file = self.weights_file.map(resolve);
+        self
+    }
+
+    /// Adds a lua module the function can load with `require(name)`.
+    pub fn with_module(mut self, name: impl Into<String>, source: impl Into<String>) -> Self {
+        self.modules.insert(name.into(), source.into());
+        self
+    }
+
+    /// Sets a parameter of the lua function.
+    pub fn with_param(mut self, name: impl Into<String>, value: Value) -> Self {
+        self.params.insert(name.into(), value);
+        self
+    }
+
+    /// Sets a setting for a registered kind to read with [`System::get_option`].
+    pub fn with_option(mut self, name: impl Into<String>, value: Value) -> Self {
+        self.options.insert(name.into(), value);
+        self
+    }
+
+    pub fn get_kind(&self) -> &str {
+        match &self.kind {
+            Some(kind) => kind,
+            None if self.function.is_some() || self.function_file.is_some() => "lua",
+            None if self.expression.is_some() => "expr",
+            None if !self.weights.is_empty() || self.weights_file.is_some() => "table",
+            None => "constant",
+        }
+    }
+
     pub fn get_base(&self) -> isize {
         self.base.unwrap_or(DEFAULT_BASE)
     }
 
+    /// The main alphabet, used at positions without an alphabet of their own.
     pub fn get_alphabet(&self) -> &Vec<(isize, char)> {
         &self.alphabet
     }
 
-    pub fn get_alphabet_letter_by_value(&self, value: isize) -> char {
-        self.alphabet
+    /// The alphabet of `position`, counted from 0 at the least significant
+    /// digit.
+    pub fn get_alphabet_at(&self, position: isize) -> &Vec<(isize, char)> {
+        let count = self.alphabets.len() as isize;
+        let index = match self.cycle && count > 0 {
+            true => Some(position.rem_euclid(count)),
+            false => Some(position).filter(|p| (0..count).contains(p)),
+        };
+        index.map_or(&self.alphabet, |index| &self.alphabets[index as usize])
+    }
+
+    pub fn get_symbol_functions(&self) -> Option<&SymbolFunctions> {
+        self.symbol_functions.as_ref()
+    }
+
+    /// Every alphabet used at some position.
+    pub fn get_alphabets(&self) -> impl Iterator<Item = &Vec<(isize, char)>> {
+        let main = (!self.cycle || self.alphabets.is_empty()).then_some(&self.alphabet);
+        main.into_iter().chain(&self.alphabets)
+    }
+
+    /// Whether any alphabet of the system writes `letter`.
+    pub fn has_symbol(&self, letter: char) -> bool {
+        self.get_alphabets()
+            .any(|alphabet| alphabet.iter().any(|x| x.1 == letter))
+    }
+
+    pub fn get_function(&self) -> Option<&str> {
+        self.function.as_deref()
+    }
+
+    pub fn get_function_file(&self) -> Option<&Path> {
+        self.function_file.as_deref()
+    }
+
+    pub fn get_libraries(&self) -> &[PathBuf] {
+        &self.libraries
+    }
+
+    pub fn get_expression(&self) -> Option<&str> {
+        self.expression.as_deref()
+    }
+
+    pub fn get_weights(&self) -> &[BigRational] {
+        &self.weights
+    }
+
+    pub fn get_weights_file(&self) -> Option<&Path> {
+        self.weights_file.as_deref()
+    }
+
+    /// The configured origin, or 0.
+    pub fn get_origin(&self) -> isize {
+        self.origin.unwrap_or_default()
+    }
+
+    /// The configured decomposition, or greedy.
+    pub fn get_decomposition(&self) -> Decomposition {
+        self.decomposition.unwrap_or_default()
+    }
+
+    /// The configured lua limits, or the defaults.
+    pub fn get_sandbox(&self) -> Sandbox {
+        self.sandbox.unwrap_or_default()
+    }
+
+    pub fn get_modules(&self) -> &BTreeMap<String, String> {
+        &self.modules
+    }
+
+    pub fn get_params(&self) -> &Map<String, Value> {
+        &self.params
+    }
+
+    pub fn get_option(&self, name: &str) -> Option<&Value> {
+        self.options.get(name)
+    }
+
+    pub fn get_alphabet_letter_by_value(
+        &self,
+        position: isize,
+        value: isize,
+    ) -> Result<char, ArithmeticError> {
+        self.get_alphabet_at(position)
             .iter()
             .find(|x| x.0 == value)
-            .expect("Value not found in alphabet")
-            .1
-            .clone()
+            .map(|x| x.1)
+            .ok_or(ArithmeticError::NoSymbol(value))
     }
 
-    pub fn get_value_by_alphabet_letter(&self, letter: char) -> isize {
-        self.alphabet
+    pub fn get_value_by_alphabet_letter(
+        &self,
+        position: isize,
+        letter: char,
+    ) -> Result<isize, ParseErrorReason> {
+        self.get_alphabet_at(position)
             .iter()
             .find(|x| x.1 == letter)
-            .expect("Letter not found in alphabet")
-            .0
+            .map(|x| x.0)
+            .ok_or(ParseErrorReason::UnknownSymbol(letter))
     }
 }
 
+fn enumerate_symbols(symbols: impl Iterator<Item = char>) -> Vec<(isize, char)> {
+    symbols
+        .enumerate()
+        .map(|(value, symbol)| (value as isize, symbol))
+        .collect()
+}
+
 impl Display for Config {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         write!(f, "Source: {:?}\nTarget: {:?}", self.source, self.target)
@@ -159,7 +680,10 @@ impl Display for System {
         write!(
             f,
             "Kind: {}\nBase: {:?}\nFunction: {:?}\nAlphabet: {:?}",
-            self.kind, self.base, self.function, self.alphabet
+            self.get_kind(),
+            self.base,
+            self.function,
+            self.alphabet
         )
     }
 }
@@ -169,96 +693,146 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_config_new() {
-        let command_line_args = CommandLineArgs {
-            source_base: Some(2),
-            target_base: Some(10),
-            source_number: "1010".to_string(),
-            source_alphabet: Some("01".to_string()),
-            target_alphabet: Some("0123456789".to_string()),
-            config_file: None,
-        };
-
-        let config = Config::new(command_line_args);
+    fn test_system_constructors() {
+        let config = Config::new(System::constant(16), System::lua("return f", "01")).unwrap();
 
-        assert_eq!(config.clone().unwrap().source.base.unwrap(), 2);
-        assert_eq!(config.clone().unwrap().target.base.unwrap(), 10);
-        assert_eq!(config.clone().unwrap().source.alphabet.len(), 2);
-        assert_eq!(config.clone().unwrap().target.alphabet.len(), 10);
+        assert_eq!(config.get_source().get_alphabet().len(), 16);
+        assert_eq!(
+            config.get_source().get_alphabet_letter_by_value(0, 15),
+            Ok('F')
+        );
+        assert_eq!(config.get_target().get_kind(), "lua");
+        assert_eq!(
+            config.get_target().get_alphabet(),
+            &vec![(0, '0'), (1, '1')]
+        );
     }
 
     #[test]
-    fn test_base_inferred_from_alphabet() {
-        let command_line_args = CommandLineArgs {
-            source_base: None,
-            target_base: None,
-            source_number: "1010".to_string(),
-            source_alphabet: Some("01".to_string()),
-            target_alphabet: Some("01234567".to_string()),
-            config_file: None,
-        };
+    fn test_custom_digit_values() {
+        let balanced = System::constant(3).with_alphabet([(-1, 'T'), (0, '0'), (1, '1')]);
 
-        let config = Config::new(command_line_args);
+        let config = Config::new(balanced, System::constant(10)).unwrap();
 
-        // The base should be inferred from the length of the alphabet
-        assert_eq!(config.clone().unwrap().source.base.unwrap(), 2);
-        assert_eq!(config.clone().unwrap().target.base.unwrap(), 8);
+        assert_eq!(
+            config.get_source().get_value_by_alphabet_letter(0, 'T'),
+            Ok(-1)
+        );
     }
 
     #[test]
-    fn test_base_defaults_to_10() {
-        let command_line_args = CommandLineArgs {
-            source_base: None,
-            target_base: None,
-            source_number: "1010".to_string(),
-            source_alphabet: None,
-            target_alphabet: None,
-            config_file: None,
-        };
+    fn test_registered_kind_and_options() {
+        let json = r#"{
+            "source": { "base": 2, "alphabet": [{ "symbol": "0", "value": 0 }, { "symbol": "1", "value": 1 }] },
+            "target": { "kind": "tally", "mark": "|", "alphabet": [{ "symbol": "|", "value": 1 }] }
+        }"#;
+        let mut registry = Registry::default();
+        registry.register("tally", |system| {
+            Ok(Box::new(crate::system::ConstantSystem::new(system)?) as Box<_>)
+        });
+
+        let config = Config::from_json_with(json, &registry).unwrap();
+
+        assert!(Config::from_json(json).is_err());
+        assert_eq!(config.get_target().get_kind(), "tally");
+        assert_eq!(
+            config.get_target().get_option("mark"),
+            Some(&Value::from("|"))
+        );
+    }
+
+    #[test]
+    fn test_sample_configs_are_valid() {
+        for config_file in [
+            "binary.json",
+            "config.json",
+            "factorial.json",
+            "factoradic.json",
+            "negative.json",
+            "table.json",
+        ] {
+            let config = Config::from_file(config_file);
+            assert!(config.is_ok(), "{}: {}", config_file, config.unwrap_err());
+        }
+    }
+
+    #[test]
+    fn test_paths_are_relative_to_config_file() {
+        let directory = Path::new(env!("CARGO_MANIFEST_DIR"));
 
-        let config = Config::new(command_line_args);
+        let config = Config::from_file(directory.join("factoradic.json")).unwrap();
 
-        // The base should default to 10 if not provided
-        assert_eq!(config.clone().unwrap().source.base.unwrap(), 10);
-        assert_eq!(config.clone().unwrap().target.base.unwrap(), 10);
+        assert_eq!(
+            config.get_source().get_function_file(),
+            Some(directory.join("lua/factoradic.lua").as_path())
+        );
+        assert_eq!(
+            config.get_source().get_libraries(),
+            [directory.join("lua/weights.lua")]
+        );
+
+        let config = Config::from_file(directory.join("table.json")).unwrap();
+
+        assert_eq!(
+            config.get_source().get_weights_file(),
+            Some(directory.join("byte.txt").as_path())
+        );
     }
 
     #[test]
-    fn test_negative_base() {
-        let command_line_args = CommandLineArgs {
-            source_base: Some(-2),
-            target_base: Some(-10),
-            source_number: "1101".to_string(),
-            source_alphabet: None,
-            target_alphabet: None,
-            config_file: None,
-        };
+    fn test_per_position_alphabets() {
+        let rods = System::constant(2)
+            .with_alphabets([vec![(0, '0'), (1, '|')], vec![(0, '0'), (1, '-')]], true);
+        let clock = System::constant(2).with_alphabets([vec![(0, 'a'), (1, 'b')]], false);
 
-        let config = Config::new(command_line_args);
+        assert_eq!(rods.get_alphabet_letter_by_value(3, 1), Ok('-'));
+        assert_eq!(rods.get_value_by_alphabet_letter(4, '|'), Ok(1));
+        assert_eq!(
+            rods.get_value_by_alphabet_letter(1, '|'),
+            Err(ParseErrorReason::UnknownSymbol('|'))
+        );
+        assert_eq!(clock.get_alphabet_letter_by_value(0, 1), Ok('b'));
+        assert_eq!(clock.get_alphabet_letter_by_value(1, 1), Ok('1'));
+        assert!(Config::new(rods, clock).is_ok());
+    }
 
-        // The base should be -2 and -10 as provided
-        assert_eq!(config.clone().unwrap().source.base.unwrap(), -2);
-        assert_eq!(config.clone().unwrap().target.base.unwrap(), -10);
+    #[test]
+    fn test_param_assignment() {
+        let config = Config::new(System::constant(2), System::constant(10)).unwrap();
+
+        let config = config
+            .with_param_assignment("source.base=16")
+            .and_then(|c| c.with_param_assignment("target.name=hex"))
+            .unwrap();
+
+        assert_eq!(config.get_source().get_params()["base"], 16);
+        assert_eq!(config.get_target().get_params()["name"], "hex");
+        for invalid in ["source.base", "base=16", "middle.base=1", "source.=1"] {
+            assert_eq!(
+                config.clone().with_param_assignment(invalid).unwrap_err(),
+                ConfigError::InvalidParam(invalid.to_string())
+            );
+        }
     }
 
     #[test]
-    fn test_error_when_base_and_number_are_negative() {
-        let command_line_args = CommandLineArgs {
-            source_base: Some(-2),
-            target_base: Some(10),
-            source_number: "-1101".to_string(),
-            source_alphabet: None,
-            target_alphabet: None,
-            config_file: None,
+    fn test_missing_config_file() {
+        let Err(ConfigError::Invalid(diagnostics)) = Config::from_file("does-not-exist.json")
+        else {
+            panic!("expected the missing file to be reported");
         };
 
-        let config = Config::new(command_line_args);
+        assert_eq!(diagnostics.len(), 1);
+        assert!(matches!(diagnostics[0].problem, Problem::Unreadable(_)));
+    }
+
+    #[test]
+    fn test_degenerate_base() {
+        let config = Config::new(System::constant(1), System::constant(10));
 
-        // An error should be returned when both base and number are negative
-        assert!(config.is_err());
         assert_eq!(
-            config.unwrap_err(),
-            "Both base and number cannot be negative"
+            config.unwrap_err().to_string(),
+            "invalid config:\n  $.source.base: base 1 cannot represent every number"
         );
     }
 }
@@ -0,0 +1,1120 @@
+use super::parse_weight;
+use crate::system::Expr;
+use serde_json::{Map, Value};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::{self, Display, Formatter},
+};
+
+/// A single problem found in a config, anchored at a JSON path such as
+/// `$.source.alphabet[3].symbol`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub path: String,
+    pub problem: Problem,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    /// The config could not be read at all.
+    Unreadable(String),
+    /// The config is not well-formed JSON.
+    Syntax {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    MissingField(&'static str),
+    WrongType {
+        expected: &'static str,
+    },
+    UnknownKind {
+        kind: String,
+        known: Vec<String>,
+    },
+    MissingFunction,
+    /// Two fields that must not be set together.
+    Conflict {
+        field: &'static str,
+        other: &'static str,
+    },
+    /// An expression that does not parse. `column` counts characters.
+    InvalidExpression {
+        column: usize,
+        message: String,
+    },
+    DegenerateBase(isize),
+    EmptyAlphabet,
+    EmptyTable,
+    /// A fraction weighting a position before the radix point.
+    FractionalWeight,
+    DuplicateSymbol {
+        symbol: String,
+        first: String,
+    },
+    DuplicateValue {
+        value: isize,
+        first: String,
+    },
+    MissingValue(isize),
+    BaseMismatch {
+        base: isize,
+        alphabet_len: usize,
+    },
+}
+
+impl Diagnostic {
+    fn new(path: impl Into<String>, problem: Problem) -> Self {
+        Self {
+            path: path.into(),
+            problem,
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.problem)
+    }
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Problem::Unreadable(reason) => write!(f, "cannot read config: {}", reason),
+            Problem::Syntax {
+                line,
+                column,
+                message,
+            } => write!(
+                f,
+                "invalid JSON at line {}, column {}: {}",
+                line, column, message
+            ),
+            Problem::MissingField(field) => write!(f, "missing field `{}`", field),
+            Problem::WrongType { expected } => write!(f, "expected {}", expected),
+            Problem::UnknownKind { kind, known } => write!(
+                f,
+                "unknown kind {:?}, expected one of {}",
+                kind,
+                known.join(", ")
+            ),
+            Problem::MissingFunction => {
+                write!(f, "kind \"lua\" requires a `function` or `function_file`")
+            }
+            Problem::Conflict { field, other } => {
+                write!(f, "`{}` cannot be set together with `{}`", field, other)
+            }
+            Problem::DegenerateBase(base) => {
+                write!(f, "base {} cannot represent every number", base)
+            }
+            Problem::InvalidExpression { column, message } => {
+                write!(f, "invalid expression: {} at column {}", message, column)
+            }
+            Problem::EmptyAlphabet => write!(f, "alphabet is empty"),
+            Problem::EmptyTable => write!(f, "table has no weights"),
+            Problem::FractionalWeight => write!(
+                f,
+                "only positions after the radix point may have fractional weights"
+            ),
+            Problem::DuplicateSymbol { symbol, first } => {
+                write!(
+                    f,
+                    "duplicate symbol {:?}, first defined at {}",
+                    symbol, first
+                )
+            }
+            Problem::DuplicateValue { value, first } => {
+                write!(
+                    f,
+                    "duplicate digit value {}, first defined at {}",
+                    value, first
+                )
+            }
+            Problem::MissingValue(value) => write!(f, "no symbol for digit value {}", value),
+            Problem::BaseMismatch { base, alphabet_len } => write!(
+                f,
+                "base {} needs {} digits but the alphabet has {}",
+                base,
+                base.unsigned_abs(),
+                alphabet_len
+            ),
+        }
+    }
+}
+
+/// Parses `json` and validates it, returning the parsed value if it is sound.
+pub fn validate_str(json: &str, kinds: &[&str]) -> Result<Value, Vec<Diagnostic>> {
+    let config: Value = serde_json::from_str(json).map_err(|e| {
+        vec![Diagnostic::new(
+            "$",
+            Problem::Syntax {
+                line: e.line(),
+                column: e.column(),
+                message: e.to_string(),
+            },
+        )]
+    })?;
+    let diagnostics = validate(&config, kinds);
+    if diagnostics.is_empty() {
+        Ok(config)
+    } else {
+        Err(diagnostics)
+    }
+}
+
+/// Checks a config for every problem it has, rather than stopping at the first.
+/// Systems must be of one of the given `kinds`.
+pub fn validate(config: &Value, kinds: &[&str]) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let Some(root) = config.as_object() else {
+        diagnostics.push(Diagnostic::new(
+            "$",
+            Problem::WrongType {
+                expected: "an object",
+            },
+        ));
+        return diagnostics;
+    };
+    for side in ["source", "target"] {
+        match root.get(side) {
+            Some(system) => {
+                validate_system(system, &format!("$.{}", side), kinds, &mut diagnostics)
+            }
+            None => diagnostics.push(Diagnostic::new("$", Problem::MissingField(side))),
+        }
+    }
+    diagnostics
+}
+
+/// The ways of picking digits a system can be configured with.
+const DECOMPOSITIONS: [&str; 3] = ["greedy", "smallest", "fewest_nonzero"];
+
+fn validate_system(system: &Value, path: &str, kinds: &[&str], diagnostics: &mut Vec<Diagnostic>) {
+    let Some(system) = system.as_object() else {
+        diagnostics.push(Diagnostic::new(
+            path,
+            Problem::WrongType {
+                expected: "an object",
+            },
+        ));
+        return;
+    };
+
+    optional_field(
+        system,
+        "function",
+        path,
+        diagnostics,
+        Value::as_str,
+        "a string",
+    );
+    optional_field(
+        system,
+        "function_file",
+        path,
+        diagnostics,
+        Value::as_str,
+        "a string",
+    );
+    if system.contains_key("function") && system.contains_key("function_file") {
+        diagnostics.push(Diagnostic::new(
+            format!("{}.function_file", path),
+            Problem::Conflict {
+                field: "function_file",
+                other: "function",
+            },
+        ));
+    }
+    let has_function = system.contains_key("function") || system.contains_key("function_file");
+    optional_field(
+        system,
+        "weights_file",
+        path,
+        diagnostics,
+        Value::as_str,
+        "a string",
+    );
+    if let Some(weights) = system.get("weights") {
+        validate_weights(weights, &format!("{}.weights", path), diagnostics);
+        if system.contains_key("weights_file") {
+            diagnostics.push(Diagnostic::new(
+                format!("{}.weights_file", path),
+                Problem::Conflict {
+                    field: "weights_file",
+                    other: "weights",
+                },
+            ));
+        }
+    }
+    let has_weights = system.contains_key("weights") || system.contains_key("weights_file");
+    let expression = optional_field(
+        system,
+        "expression",
+        path,
+        diagnostics,
+        Value::as_str,
+        "a string",
+    );
+    if let Some(Err(error)) = expression.map(Expr::parse) {
+        diagnostics.push(Diagnostic::new(
+            format!("{}.expression", path),
+            Problem::InvalidExpression {
+                column: error.column,
+                message: error.message,
+            },
+        ));
+    }
+    let base = optional_field(system, "base", path, diagnostics, as_isize, "an integer");
+    let origin = optional_field(system, "origin", path, diagnostics, as_isize, "an integer");
+    let kind = match optional_field(system, "kind", path, diagnostics, Value::as_str, "a string") {
+        Some(kind) => kind,
+        None if has_function => "lua",
+        None if system.contains_key("expression") => "expr",
+        None if has_weights => "table",
+        None => "constant",
+    };
+
+    match kind {
+        kind if !kinds.contains(&kind) => diagnostics.push(Diagnostic::new(
+            format!("{}.kind", path),
+            Problem::UnknownKind {
+                kind: kind.to_string(),
+                known: kinds.iter().map(|k| k.to_string()).collect(),
+            },
+        )),
+        "constant" => match base {
+            Some(base @ -1..=1) => diagnostics.push(Diagnostic::new(
+                format!("{}.base", path),
+                Problem::DegenerateBase(base),
+            )),
+            Some(_) => {}
+            None if system.contains_key("base") => {}
+            None => diagnostics.push(Diagnostic::new(path, Problem::MissingField("base"))),
+        },
+        "lua" if !has_function => diagnostics.push(Diagnostic::new(path, Problem::MissingFunction)),
+        "expr" if !system.contains_key("expression") => {
+            diagnostics.push(Diagnostic::new(path, Problem::MissingField("expression")))
+        }
+        "table" if !has_weights => {
+            diagnostics.push(Diagnostic::new(path, Problem::MissingField("weights")))
+        }
+        _ => {}
+    }
+    if let Some(weights) = system.get("weights").and_then(Value::as_array) {
+        // Table weights before the origin are those after the radix point.
+        let first_whole = match kind {
+            "table" => origin.and_then(|origin| usize::try_from(origin).ok()),
+            _ => None,
+        };
+        for (index, weight) in weights.iter().enumerate().skip(first_whole.unwrap_or(0)) {
+            if parse_weight(weight).is_some_and(|weight| !weight.is_integer()) {
+                diagnostics.push(Diagnostic::new(
+                    format!("{}.weights[{}]", path, index),
+                    Problem::FractionalWeight,
+                ));
+            }
+        }
+    }
+
+    let decomposition = optional_field(
+        system,
+        "decomposition",
+        path,
+        diagnostics,
+        Value::as_str,
+        "a string",
+    );
+    if let Some(decomposition) = decomposition {
+        if !DECOMPOSITIONS.contains(&decomposition) {
+            diagnostics.push(Diagnostic::new(
+                format!("{}.decomposition", path),
+                Problem::WrongType {
+                    expected: "\"greedy\", \"smallest\" or \"fewest_nonzero\"",
+                },
+            ));
+        }
+    }
+    if let Some(sandbox) = system.get("sandbox") {
+        validate_sandbox(sandbox, &format!("{}.sandbox", path), diagnostics);
+    }
+    optional_field(
+        system,
+        "params",
+        path,
+        diagnostics,
+        Value::as_object,
+        "an object",
+    );
+    if let Some(modules) = system.get("modules") {
+        validate_modules(modules, &format!("{}.modules", path), diagnostics);
+    }
+    if let Some(libraries) = system.get("libraries") {
+        validate_libraries(libraries, &format!("{}.libraries", path), diagnostics);
+    }
+
+    let cycle = optional_field(
+        system,
+        "cycle",
+        path,
+        diagnostics,
+        Value::as_bool,
+        "a boolean",
+    );
+    let mut alphabets = Vec::new();
+    match system.get("alphabets") {
+        None => {}
+        Some(Value::Array(list)) if list.is_empty() => diagnostics.push(Diagnostic::new(
+            format!("{}.alphabets", path),
+            Problem::EmptyAlphabet,
+        )),
+        Some(Value::Array(list)) => alphabets.extend(
+            list.iter()
+                .enumerate()
+                .map(|(index, alphabet)| (format!("{}.alphabets[{}]", path, index), alphabet)),
+        ),
+        Some(_) => diagnostics.push(Diagnostic::new(
+            format!("{}.alphabets", path),
+            Problem::WrongType {
+                expected: "an array",
+            },
+        )),
+    }
+    let symbol_functions = system.get("symbol_functions").map(|functions| {
+        let functions_path = format!("{}.symbol_functions", path);
+        validate_symbol_functions(functions, &functions_path, diagnostics)
+    });
+    // Cycling per-position alphabets and symbol functions cover every
+    // position on their own.
+    match system.get("alphabet") {
+        Some(alphabet) => alphabets.insert(0, (format!("{}.alphabet", path), alphabet)),
+        None if cycle == Some(true) && !alphabets.is_empty() => {}
+        None if symbol_functions.is_some() => {}
+        None => diagnostics.push(Diagnostic::new(path, Problem::MissingField("alphabet"))),
+    }
+    if let (Some(base), Some(Some(alphabet_len))) = (base, symbol_functions) {
+        if base.unsigned_abs() > 1 && base.unsigned_abs() != alphabet_len {
+            diagnostics.push(Diagnostic::new(
+                format!("{}.base", path),
+                Problem::BaseMismatch { base, alphabet_len },
+            ));
+        }
+    }
+
+    for (alphabet_path, alphabet) in alphabets {
+        let Some(alphabet_len) = validate_alphabet(alphabet, &alphabet_path, diagnostics) else {
+            continue;
+        };
+        if let Some(base) = base {
+            if base.unsigned_abs() > 1 && base.unsigned_abs() != alphabet_len {
+                diagnostics.push(Diagnostic::new(
+                    format!("{}.base", path),
+                    Problem::BaseMismatch { base, alphabet_len },
+                ));
+            }
+        }
+    }
+}
+
+/// Validates the alphabet entries and returns the number of distinct digits if
+/// the alphabet is well-formed enough to compare against a base.
+fn validate_alphabet(
+    alphabet: &Value,
+    path: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Option<usize> {
+    let Some(letters) = alphabet.as_array() else {
+        diagnostics.push(Diagnostic::new(
+            path,
+            Problem::WrongType {
+                expected: "an array",
+            },
+        ));
+        return None;
+    };
+    if letters.is_empty() {
+        diagnostics.push(Diagnostic::new(path, Problem::EmptyAlphabet));
+        return None;
+    }
+
+    let mut symbols: HashMap<&str, String> = HashMap::new();
+    let mut values: HashMap<isize, String> = HashMap::new();
+    for (index, letter) in letters.iter().enumerate() {
+        let letter_path = format!("{}[{}]", path, index);
+        let Some(letter) = letter.as_object() else {
+            diagnostics.push(Diagnostic::new(
+                letter_path,
+                Problem::WrongType {
+                    expected: "an object",
+                },
+            ));
+            continue;
+        };
+
+        let symbol_path = format!("{}.symbol", letter_path);
+        match letter.get("symbol") {
+            None => diagnostics.push(Diagnostic::new(
+                &letter_path,
+                Problem::MissingField("symbol"),
+            )),
+            Some(symbol) => match symbol.as_str().filter(|s| s.chars().count() == 1) {
+                None => diagnostics.push(Diagnostic::new(
+                    symbol_path,
+                    Problem::WrongType {
+                        expected: "a single character",
+                    },
+                )),
+                Some(symbol) => match symbols.get(symbol) {
+                    Some(first) => diagnostics.push(Diagnostic::new(
+                        symbol_path,
+                        Problem::DuplicateSymbol {
+                            symbol: symbol.to_string(),
+                            first: first.clone(),
+                        },
+                    )),
+                    None => {
+                        symbols.insert(symbol, symbol_path);
+                    }
+                },
+            },
+        }
+
+        let value_path = format!("{}.value", letter_path);
+        match letter.get("value") {
+            None => diagnostics.push(Diagnostic::new(
+                &letter_path,
+                Problem::MissingField("value"),
+            )),
+            Some(value) => match as_isize(value) {
+                None => diagnostics.push(Diagnostic::new(
+                    value_path,
+                    Problem::WrongType {
+                        expected: "an integer",
+                    },
+                )),
+                Some(value) => match values.get(&value) {
+                    Some(first) => diagnostics.push(Diagnostic::new(
+                        value_path,
+                        Problem::DuplicateValue {
+                            value,
+                            first: first.clone(),
+                        },
+                    )),
+                    None => {
+                        values.insert(value, value_path);
+                    }
+                },
+            },
+        }
+    }
+
+    let values: BTreeSet<isize> = values.into_keys().collect();
+    if let (Some(&min), Some(&max)) = (values.first(), values.last()) {
+        for missing in (min..=max).filter(|v| !values.contains(v)) {
+            diagnostics.push(Diagnostic::new(path, Problem::MissingValue(missing)));
+        }
+    }
+    Some(values.len())
+}
+
+fn validate_sandbox(sandbox: &Value, path: &str, diagnostics: &mut Vec<Diagnostic>) {
+    let Some(sandbox) = sandbox.as_object() else {
+        diagnostics.push(Diagnostic::new(
+            path,
+            Problem::WrongType {
+                expected: "an object",
+            },
+        ));
+        return;
+    };
+    for limit in ["instruction_limit", "memory_limit", "timeout_ms"] {
+        optional_field(
+            sandbox,
+            limit,
+            path,
+            diagnostics,
+            Value::as_u64,
+            "a non-negative integer",
+        );
+    }
+}
+
+/// Validates the lua functions generating symbols and returns the number of
+/// digit values they cover, if known.
+fn validate_symbol_functions(
+    functions: &Value,
+    path: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Option<usize> {
+    let Some(functions) = functions.as_object() else {
+        diagnostics.push(Diagnostic::new(
+            path,
+            Problem::WrongType {
+                expected: "an object",
+            },
+        ));
+        return None;
+    };
+    for field in ["symbol", "value"] {
+        if !functions.contains_key(field) {
+            diagnostics.push(Diagnostic::new(path, Problem::MissingField(field)));
+        }
+        optional_field(
+            functions,
+            field,
+            path,
+            diagnostics,
+            Value::as_str,
+            "a string",
+        );
+    }
+    optional_field(
+        functions,
+        "pattern",
+        path,
+        diagnostics,
+        Value::as_str,
+        "a string",
+    );
+    let [min, max] = ["min_value", "max_value"].map(|field| {
+        if !functions.contains_key(field) {
+            diagnostics.push(Diagnostic::new(path, Problem::MissingField(field)));
+        }
+        optional_field(functions, field, path, diagnostics, as_isize, "an integer")
+    });
+    match (min, max) {
+        (Some(min), Some(max)) if min > max => {
+            diagnostics.push(Diagnostic::new(path, Problem::EmptyAlphabet));
+            None
+        }
+        (Some(min), Some(max)) => Some(max.abs_diff(min) + 1),
+        _ => None,
+    }
+}
+
+/// Weights are integers, or strings with integers too large for JSON or with
+/// fractions such as `"1/6"`.
+fn validate_weights(weights: &Value, path: &str, diagnostics: &mut Vec<Diagnostic>) {
+    let Some(weights) = weights.as_array() else {
+        diagnostics.push(Diagnostic::new(
+            path,
+            Problem::WrongType {
+                expected: "an array",
+            },
+        ));
+        return;
+    };
+    if weights.is_empty() {
+        diagnostics.push(Diagnostic::new(path, Problem::EmptyTable));
+    }
+    for (index, weight) in weights.iter().enumerate() {
+        if parse_weight(weight).is_none() {
+            diagnostics.push(Diagnostic::new(
+                format!("{}[{}]", path, index),
+                Problem::WrongType {
+                    expected: "an integer, a decimal string or a fraction",
+                },
+            ));
+        }
+    }
+}
+
+/// Modules map names to lua source code.
+fn validate_modules(modules: &Value, path: &str, diagnostics: &mut Vec<Diagnostic>) {
+    let Some(modules) = modules.as_object() else {
+        diagnostics.push(Diagnostic::new(
+            path,
+            Problem::WrongType {
+                expected: "an object",
+            },
+        ));
+        return;
+    };
+    for (name, source) in modules {
+        if !source.is_string() {
+            diagnostics.push(Diagnostic::new(
+                format!("{}.{}", path, name),
+                Problem::WrongType {
+                    expected: "a string",
+                },
+            ));
+        }
+    }
+}
+
+/// Libraries are paths of lua files.
+fn validate_libraries(libraries: &Value, path: &str, diagnostics: &mut Vec<Diagnostic>) {
+    let Some(libraries) = libraries.as_array() else {
+        diagnostics.push(Diagnostic::new(
+            path,
+            Problem::WrongType {
+                expected: "an array",
+            },
+        ));
+        return;
+    };
+    for (index, library) in libraries.iter().enumerate() {
+        if !library.is_string() {
+            diagnostics.push(Diagnostic::new(
+                format!("{}[{}]", path, index),
+                Problem::WrongType {
+                    expected: "a string",
+                },
+            ));
+        }
+    }
+}
+
+fn optional_field<'a, T>(
+    system: &'a Map<String, Value>,
+    field: &'static str,
+    path: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+    convert: fn(&'a Value) -> Option<T>,
+    expected: &'static str,
+) -> Option<T> {
+    let value = system.get(field)?;
+    let converted = convert(value);
+    if converted.is_none() {
+        diagnostics.push(Diagnostic::new(
+            format!("{}.{}", path, field),
+            Problem::WrongType { expected },
+        ));
+    }
+    converted
+}
+
+fn as_isize(value: &Value) -> Option<isize> {
+    value.as_i64().and_then(|v| isize::try_from(v).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const KINDS: [&str; 4] = ["constant", "expr", "lua", "table"];
+
+    fn letters(symbols: &str) -> Value {
+        symbols
+            .chars()
+            .enumerate()
+            .map(|(value, symbol)| json!({ "symbol": symbol.to_string(), "value": value }))
+            .collect()
+    }
+
+    fn problems(config: Value) -> Vec<(String, Problem)> {
+        validate(&config, &KINDS)
+            .into_iter()
+            .map(|d| (d.path, d.problem))
+            .collect()
+    }
+
+    #[test]
+    fn test_sound_config_has_no_diagnostics() {
+        let config = json!({
+            "source": { "kind": "constant", "base": 2, "alphabet": letters("01") },
+            "target": { "kind": "lua", "function": "return function (n) return 1 end", "alphabet": letters("0123") },
+        });
+
+        assert!(validate(&config, &KINDS).is_empty());
+    }
+
+    #[test]
+    fn test_kind_is_inferred_from_function() {
+        let config = json!({
+            "source": { "function": "return function (n) return 1 end", "alphabet": letters("01") },
+            "target": { "base": 10, "alphabet": letters("0123456789") },
+        });
+
+        assert!(validate(&config, &KINDS).is_empty());
+    }
+
+    #[test]
+    fn test_every_problem_is_reported() {
+        let config = json!({
+            "source": {
+                "kind": "constant",
+                "base": 3,
+                "alphabet": [
+                    { "symbol": "O", "value": 0 },
+                    { "symbol": "O", "value": 2 },
+                    { "symbol": "L", "value": 2 },
+                ]
+            },
+            "target": { "kind": "roman", "alphabet": letters("IVX") },
+        });
+
+        assert_eq!(
+            problems(config),
+            vec![
+                (
+                    "$.source.alphabet[1].symbol".to_string(),
+                    Problem::DuplicateSymbol {
+                        symbol: "O".to_string(),
+                        first: "$.source.alphabet[0].symbol".to_string()
+                    }
+                ),
+                (
+                    "$.source.alphabet[2].value".to_string(),
+                    Problem::DuplicateValue {
+                        value: 2,
+                        first: "$.source.alphabet[1].value".to_string()
+                    }
+                ),
+                ("$.source.alphabet".to_string(), Problem::MissingValue(1)),
+                (
+                    "$.source.base".to_string(),
+                    Problem::BaseMismatch {
+                        base: 3,
+                        alphabet_len: 2
+                    }
+                ),
+                (
+                    "$.target.kind".to_string(),
+                    Problem::UnknownKind {
+                        kind: "roman".to_string(),
+                        known: KINDS.map(String::from).to_vec()
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lua_without_function() {
+        let config = json!({
+            "source": { "kind": "lua", "alphabet": letters("01") },
+            "target": { "base": 2, "alphabet": letters("01") },
+        });
+
+        assert_eq!(
+            problems(config),
+            vec![("$.source".to_string(), Problem::MissingFunction)]
+        );
+    }
+
+    #[test]
+    fn test_degenerate_bases() {
+        for base in [-1, 0, 1] {
+            let config = json!({
+                "source": { "kind": "constant", "base": base, "alphabet": letters("0") },
+                "target": { "base": 2, "alphabet": letters("01") },
+            });
+
+            assert_eq!(
+                problems(config),
+                vec![("$.source.base".to_string(), Problem::DegenerateBase(base))]
+            );
+        }
+    }
+
+    #[test]
+    fn test_wrong_types() {
+        let config = json!({
+            "source": { "base": "2", "alphabet": [{ "symbol": "01", "value": 0 }] },
+            "target": { "base": 2 },
+        });
+
+        assert_eq!(
+            problems(config),
+            vec![
+                (
+                    "$.source.base".to_string(),
+                    Problem::WrongType {
+                        expected: "an integer"
+                    }
+                ),
+                (
+                    "$.source.alphabet[0].symbol".to_string(),
+                    Problem::WrongType {
+                        expected: "a single character"
+                    }
+                ),
+                ("$.target".to_string(), Problem::MissingField("alphabet")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sandbox_limits() {
+        let config = json!({
+            "source": {
+                "function": "return function (n) return 1 end",
+                "sandbox": { "instruction_limit": 1000, "timeout_ms": -1 },
+                "alphabet": letters("01")
+            },
+            "target": { "base": 2, "alphabet": letters("01") },
+        });
+
+        assert_eq!(
+            problems(config),
+            vec![(
+                "$.source.sandbox.timeout_ms".to_string(),
+                Problem::WrongType {
+                    expected: "a non-negative integer"
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn test_modules_are_lua_source() {
+        let config = json!({
+            "source": {
+                "function": "return require('helpers').weight",
+                "modules": { "helpers": "return {}", "broken": 1 },
+                "alphabet": letters("01")
+            },
+            "target": { "base": 2, "alphabet": letters("01"), "modules": [] },
+        });
+
+        assert_eq!(
+            problems(config),
+            vec![
+                (
+                    "$.source.modules.broken".to_string(),
+                    Problem::WrongType {
+                        expected: "a string"
+                    }
+                ),
+                (
+                    "$.target.modules".to_string(),
+                    Problem::WrongType {
+                        expected: "an object"
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_function_files() {
+        let config = json!({
+            "source": { "function_file": "f.lua", "libraries": ["lib.lua", 2], "alphabet": letters("01") },
+            "target": { "function": "return f", "function_file": "f.lua", "alphabet": letters("01") },
+        });
+
+        assert_eq!(
+            problems(config),
+            vec![
+                (
+                    "$.source.libraries[1]".to_string(),
+                    Problem::WrongType {
+                        expected: "a string"
+                    }
+                ),
+                (
+                    "$.target.function_file".to_string(),
+                    Problem::Conflict {
+                        field: "function_file",
+                        other: "function"
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tables() {
+        let config = json!({
+            "source": { "weights": [1, "4096", "2^16", true], "alphabet": letters("01") },
+            "target": { "kind": "table", "alphabet": letters("01") },
+        });
+        let fractions = json!({
+            "source": { "weights": ["1/2", 1, "3/2"], "origin": 1, "alphabet": letters("01") },
+            "target": { "weights": [1, 2], "origin": "1", "alphabet": letters("01") },
+        });
+        let files = json!({
+            "source": { "weights_file": "word.txt", "alphabet": letters("01") },
+            "target": { "weights": [], "weights_file": "word.txt", "alphabet": letters("01") },
+        });
+
+        assert_eq!(
+            problems(config),
+            vec![
+                (
+                    "$.source.weights[2]".to_string(),
+                    Problem::WrongType {
+                        expected: "an integer, a decimal string or a fraction"
+                    }
+                ),
+                (
+                    "$.source.weights[3]".to_string(),
+                    Problem::WrongType {
+                        expected: "an integer, a decimal string or a fraction"
+                    }
+                ),
+                ("$.target".to_string(), Problem::MissingField("weights")),
+            ]
+        );
+        assert_eq!(
+            problems(fractions),
+            vec![
+                ("$.source.weights[2]".to_string(), Problem::FractionalWeight),
+                (
+                    "$.target.origin".to_string(),
+                    Problem::WrongType {
+                        expected: "an integer"
+                    }
+                ),
+            ]
+        );
+        assert_eq!(
+            problems(files),
+            vec![
+                ("$.target.weights".to_string(), Problem::EmptyTable),
+                (
+                    "$.target.weights_file".to_string(),
+                    Problem::Conflict {
+                        field: "weights_file",
+                        other: "weights"
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decompositions() {
+        let config = json!({
+            "source": { "weights": [1, 3, 4], "decomposition": "fewest_nonzero", "alphabet": letters("01") },
+            "target": { "weights": [1, 3, 4], "decomposition": "shortest", "alphabet": letters("01") },
+        });
+
+        assert_eq!(
+            problems(config),
+            vec![(
+                "$.target.decomposition".to_string(),
+                Problem::WrongType {
+                    expected: "\"greedy\", \"smallest\" or \"fewest_nonzero\""
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn test_expressions() {
+        let config = json!({
+            "source": { "expression": "(n+1)!", "alphabet": letters("0123") },
+            "target": { "expression": "16^", "alphabet": letters("01") },
+        });
+        let missing = json!({
+            "source": { "kind": "expr", "alphabet": letters("01") },
+            "target": { "expression": 16, "alphabet": letters("01") },
+        });
+
+        assert_eq!(
+            problems(config),
+            vec![(
+                "$.target.expression".to_string(),
+                Problem::InvalidExpression {
+                    column: 3,
+                    message: "expected a number, `n`, `w(...)` or `(`".to_string()
+                }
+            )]
+        );
+        assert_eq!(
+            problems(missing),
+            vec![
+                ("$.source".to_string(), Problem::MissingField("expression")),
+                (
+                    "$.target.expression".to_string(),
+                    Problem::WrongType {
+                        expected: "a string"
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_per_position_alphabets() {
+        let config = json!({
+            "source": {
+                "base": 2,
+                "alphabets": [letters("01"), letters("ab")],
+                "cycle": true
+            },
+            "target": {
+                "base": 2,
+                "alphabet": letters("01"),
+                "alphabets": [letters("abc"), []]
+            },
+        });
+
+        assert_eq!(
+            problems(config),
+            vec![
+                (
+                    "$.target.base".to_string(),
+                    Problem::BaseMismatch {
+                        base: 2,
+                        alphabet_len: 3
+                    }
+                ),
+                ("$.target.alphabets[1]".to_string(), Problem::EmptyAlphabet),
+            ]
+        );
+        assert_eq!(
+            problems(json!({
+                "source": { "base": 2, "alphabets": [letters("01")] },
+                "target": { "base": 2, "alphabet": letters("01") },
+            })),
+            vec![("$.source".to_string(), Problem::MissingField("alphabet"))]
+        );
+    }
+
+    #[test]
+    fn test_symbol_functions() {
+        let config = json!({
+            "source": {
+                "base": 1000,
+                "symbol_functions": {
+                    "symbol": "return function (v) return '<' .. v .. '>' end",
+                    "value": "return function (s) return tonumber(s:match('^<(%d+)>$')) end",
+                    "pattern": "<%d+>",
+                    "min_value": 0,
+                    "max_value": 999
+                }
+            },
+            "target": {
+                "base": 10,
+                "symbol_functions": { "symbol": 1, "min_value": 0, "max_value": 15 }
+            },
+        });
+
+        assert_eq!(
+            problems(config),
+            vec![
+                (
+                    "$.target.symbol_functions.symbol".to_string(),
+                    Problem::WrongType {
+                        expected: "a string"
+                    }
+                ),
+                (
+                    "$.target.symbol_functions".to_string(),
+                    Problem::MissingField("value")
+                ),
+                (
+                    "$.target.base".to_string(),
+                    Problem::BaseMismatch {
+                        base: 10,
+                        alphabet_len: 16
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_syntax_error_has_position() {
+        let diagnostics = validate_str("{\n  \"source\": }", &KINDS).unwrap_err();
+
+        assert!(matches!(
+            diagnostics[0].problem,
+            Problem::Syntax { line: 2, .. }
+        ));
+    }
+}
@@ -1,2 +1,9 @@
-pub(crate) const DECIMAL_ALPHABET: &str = "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyzАБВГДЕЖЗИЙКЛМНОПРСТУФХЦЧШЩЪЫЬЭЮЯабвгдежзийклмнопрстуфхцчшщъыьэюя";
-pub(crate) const DEFAULT_BASE: isize = 10;
\ No newline at end of file
+/// Digit symbols in order of value, used when a system gives only a base.
+pub const DECIMAL_ALPHABET: &str = "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyzАБВГДЕЖЗИЙКЛМНОПРСТУФХЦЧШЩЪЫЬЭЮЯабвгдежзийклмнопрстуфхцчшщъыьэюя";
+
+/// The base used when neither a base nor an alphabet is given.
+pub const DEFAULT_BASE: isize = 10;
+
+/// The symbol separating the digits before and after the radix point, unless
+/// a system writes it as a digit.
+pub const RADIX_POINT: char = '.';
@@ -1,207 +1,210 @@
-mod bignum;
+mod batch;
+mod columns;
+mod service;
 
 use crate::config::Config;
-use bignum::LUA_BIGNUM;
-
-use mlua::prelude::*;
+use crate::error::Result;
+use crate::system::{Digit, NumeralSystem, Registry};
 use num_bigint::BigInt;
-use num_traits::ToPrimitive;
-use std::io::{Error, ErrorKind};
-use std::io::Result;
-use num_integer::Integer;
+use num_rational::BigRational;
+use serde_json::{json, Value};
+
+pub use batch::{BatchSummary, LineError};
+pub use columns::{Column, Columns};
+pub use service::Service;
 
+/// Converts numbers between the source and target systems of a [`Config`].
 #[derive(Debug)]
-pub(crate) struct Converter<'a> {
-    config: &'a Config,
-    lua: Lua,
-    number: BigInt,
+pub struct Converter {
+    source: Box<dyn NumeralSystem>,
+    target: Box<dyn NumeralSystem>,
+}
+
+/// A converted number with the digits it is written with in the target system.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conversion {
+    pub input: String,
+    pub number: BigRational,
+    pub output: String,
+    /// `None` if the target system does not write numbers digit by digit.
+    pub digits: Option<Vec<Digit>>,
 }
 
-impl<'a> Converter<'a> {
-    pub fn new(config: &'a Config, number: BigInt) -> Self {
-        let lua = Lua::new();
-        lua.load(LUA_BIGNUM).exec().expect("Error loading lua bignum library");
-        Self { config, lua, number }
+impl Conversion {
+    /// The conversion as a JSON object. Numbers too large for JSON, the
+    /// number converted and the weights, are written as decimal strings or
+    /// fractions such as `"5/4"`.
+    pub fn to_json(&self) -> Value {
+        let digits = self.digits.as_ref().map(|digits| {
+            digits
+                .iter()
+                .map(|digit| {
+                    json!({
+                        "position": digit.position,
+                        "symbol": digit.symbol,
+                        "value": digit.value,
+                        "weight": digit.weight.to_string(),
+                    })
+                })
+                .collect::<Vec<_>>()
+        });
+        json!({
+            "input": self.input,
+            "number": self.number.to_string(),
+            "target": self.output,
+            "digits": digits,
+        })
     }
+}
 
-    fn calculate_base(self, function: &str, position: isize) -> Result<BigInt> {
-        let calculate_base: mlua::Function = match self.lua.load(function).eval() {
-            Ok(f) => f,
-            Err(e) => panic!("Error loading lua function: {}", e),
-        };
-        let big_string: String = match calculate_base.call(position) {
-            Ok(v) => v,
-            Err(e) => panic!("Error calling lua function: {}", e),
-        };
-        let big_base = BigInt::parse_bytes(big_string.as_bytes(), 10)
-            .ok_or(Error::new(ErrorKind::InvalidData, "Invalid value"))?;
-        Ok(big_base)
+impl Converter {
+    /// Builds the systems of `config` with the built-in kinds.
+    pub fn new(config: &Config) -> Result<Self> {
+        Self::with_registry(config, &Registry::default())
     }
 
-    pub fn print_number_in_target_base(&self) -> String {
-        let base = self.config.get_source_base();
-        let mut number = self.number.clone();
-        let mut result = Vec::new();
+    /// Builds the systems of `config` with the kinds known to `registry`.
+    pub fn with_registry(config: &Config, registry: &Registry) -> Result<Self> {
+        Ok(Self {
+            source: registry.build(config.get_source())?,
+            target: registry.build(config.get_target())?,
+        })
+    }
 
-        while number != BigInt::from(0) {
-            let (quotient, remainder) = number.div_rem(&BigInt::from(base));
-            number = quotient;
-            result.push(remainder.to_str_radix(10));
-        }
+    pub fn source(&self) -> &dyn NumeralSystem {
+        self.source.as_ref()
+    }
 
-        result.reverse();
-        result.join("")
+    pub fn target(&self) -> &dyn NumeralSystem {
+        self.target.as_ref()
     }
 
-    pub fn print_number_in_target_alphabet(&self) -> String {
-        let alphabet = &self.config.get_target_alphabet();
-        let base = BigInt::from(alphabet.len());
-        let mut number = self.number.clone();
-        let mut result = String::new();
+    /// Reads `input` in the source system and writes it in the target system.
+    /// Digits after a radix point are converted exactly.
+    pub fn convert(&self, input: &str) -> Result<String> {
+        let number = self.source.parse_rational(input)?;
+        self.target.render_rational(&number)
+    }
 
-        while number != BigInt::from(0) {
-            let (quotient, remainder) = number.div_rem(&base);
-            number = quotient;
-            let digit = remainder.to_usize().unwrap();
-            result.push(alphabet[digit].1.clone());
-        }
+    /// Converts `input` like [`Converter::convert`], also giving the number
+    /// in between and the digits of the result.
+    pub fn convert_with_digits(&self, input: &str) -> Result<Conversion> {
+        let number = self.source.parse_rational(input)?;
+        let output = self.target.render_rational(&number)?;
+        Ok(Conversion {
+            input: input.to_string(),
+            digits: self.target.digits(&output)?,
+            number,
+            output,
+        })
+    }
 
-        result.chars().rev().collect::<String>()
+    /// Reads `input` as a number written in the source system.
+    pub fn parse(&self, input: &str) -> Result<BigInt> {
+        self.source.parse(input)
     }
 
+    /// Writes `number` in the target system.
+    pub fn render(&self, number: &BigInt) -> Result<String> {
+        self.target.render(number)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::CommandLineArgs;
-
     use super::*;
-    use num_bigint::BigInt;
-    use std:: str::FromStr;
+    use crate::config::System;
+    use crate::error::{Error, ParseErrorReason};
+    use std::str::FromStr;
 
-    #[test]
-    fn test_calculate_base_1() {
-        let lua_function = r#"f = function (n) n = BigNum.new(n) base = BigNum.new(10^6) return tostring(base^n) end return f"#;
-
-        let command_line_args = CommandLineArgs {
-            source_base: Some(2),
-            target_base: Some(10),
-            source_number: "1010".to_string(),
-            source_alphabet: Some("01".to_string()),
-            target_alphabet: Some("0123456789".to_string()),
-            config_file: None,
-        };
-
-        let config = Config::new(command_line_args);
-        let number = BigInt::from(0); // Replace with your number
-        let config = &config.unwrap();
-        let converter = Converter::new(&config, number);
-
-        assert_eq!(converter.calculate_base(lua_function, 1).unwrap(), BigInt::from(1000000));
+    fn bases_config(source_base: isize, target_base: isize) -> Config {
+        Config::new(System::constant(source_base), System::constant(target_base)).unwrap()
     }
 
     #[test]
-    fn test_calculate_base_2() {
-        let lua_function = r#"f = function (n) n = BigNum.new(n) base = BigNum.new(10^6) return tostring(base^n) end return f"#;
-
-        let command_line_args = CommandLineArgs {
-            source_base: Some(2),
-            target_base: Some(10),
-            source_number: "1010".to_string(),
-            source_alphabet: Some("01".to_string()),
-            target_alphabet: Some("0123456789".to_string()),
-            config_file: None,
-        };
-
-        let config = Config::new(command_line_args);
-        let number = BigInt::from(0); // Replace with your number
-        let config = &config.unwrap();
-        let converter = Converter::new(&config, number);
+    fn test_convert_binary_to_decimal() {
+        let config = bases_config(2, 10);
+        let converter = Converter::new(&config).unwrap();
 
-        assert_eq!(converter.calculate_base(lua_function, 2).unwrap(), BigInt::from_str("1000000000000").unwrap());
+        assert_eq!(converter.convert("1010").unwrap(), "10");
+        assert_eq!(converter.convert("0").unwrap(), "0");
+        assert_eq!(converter.convert("-1010").unwrap(), "-10");
     }
 
     #[test]
-    fn test_calculate_base_0() {
-        let lua_function = r#"f = function (n) n = BigNum.new(n) base = BigNum.new(10^6) return tostring(base^n) end return f"#;
-
-        let command_line_args = CommandLineArgs {
-            source_base: Some(2),
-            target_base: Some(10),
-            source_number: "1010".to_string(),
-            source_alphabet: Some("01".to_string()),
-            target_alphabet: Some("0123456789".to_string()),
-            config_file: None,
-        };
-
-        let config = Config::new(command_line_args);
-        let number = BigInt::from(0); // Replace with your number
-        let config = &config.unwrap();
-        let converter = Converter::new(&config, number);
+    fn test_convert_to_negative_base() {
+        let config = bases_config(10, -2);
+        let converter = Converter::new(&config).unwrap();
 
-        assert_eq!(converter.calculate_base(lua_function, 0).unwrap(), BigInt::from(1));
+        assert_eq!(converter.convert("6").unwrap(), "11010");
+        assert_eq!(converter.convert("-3").unwrap(), "1101");
     }
 
     #[test]
-    fn test_factorial_1() {
-        let lua_function = r#"f = function (n) n = BigNum.new(n) one = BigNum.new(1) if n <= one then return tostring(one) else return tostring(n * f(n - one)) end end return f"#;
-
-        let command_line_args = CommandLineArgs {
-            source_base: Some(2),
-            target_base: Some(10),
-            source_number: "1".to_string(),
-            source_alphabet: Some("01".to_string()),
-            target_alphabet: Some("0123456789".to_string()),
-            config_file: None,
-        };
+    fn test_parse_error_has_position() {
+        let config = bases_config(2, 10);
+        let converter = Converter::new(&config).unwrap();
 
-        let config = Config::new(command_line_args);
-        let number = BigInt::from(1);
-        let config = &config.unwrap();
-        let converter = Converter::new(&config, number);
+        let error = converter.convert("-1021").unwrap_err();
 
-        assert_eq!(converter.calculate_base(lua_function, 1).unwrap(), BigInt::from(1));
+        let Error::Parse(error) = error else {
+            panic!("expected a parse error, got {:?}", error);
+        };
+        assert_eq!(error.position, 3);
+        assert_eq!(error.reason, ParseErrorReason::UnknownSymbol('2'));
+        assert_eq!(error.underline(), "-1021\n   ^");
     }
 
     #[test]
-    fn test_factorial_2() {
-        let lua_function = r#"f = function (n) n = BigNum.new(n) one = BigNum.new(1) if n <= one then return tostring(one) else return tostring(n * f(n - one)) end end return f"#;
-
-        let command_line_args = CommandLineArgs {
-            source_base: Some(2),
-            target_base: Some(10),
-            source_number: "2".to_string(),
-            source_alphabet: Some("01".to_string()),
-            target_alphabet: Some("0123456789".to_string()),
-            config_file: None,
-        };
+    fn test_lua_weights() {
+        let config = Config::from_file("config.json").unwrap();
+        let converter = Converter::new(&config).unwrap();
 
-        let config = Config::new(command_line_args);
-        let number = BigInt::from(2);
-        let config = &config.unwrap();
-        let converter = Converter::new(&config, number);
+        let number = converter.parse("101").unwrap();
 
-        assert_eq!(converter.calculate_base(lua_function, 2).unwrap(), BigInt::from(2));
+        assert_eq!(number, BigInt::from_str("1000000000001").unwrap());
+        assert_eq!(converter.render(&BigInt::from(13)).unwrap(), "1101");
     }
 
     #[test]
-    fn test_factorial_3() {
-        let lua_function = r#"f = function (n) n = BigNum.new(n) one = BigNum.new(1) if n <= one then return tostring(one) else return tostring(n * f(n - one)) end end return f"#;
-
-        let command_line_args = CommandLineArgs {
-            source_base: Some(2),
-            target_base: Some(10),
-            source_number: "3".to_string(),
-            source_alphabet: Some("01".to_string()),
-            target_alphabet: Some("0123456789".to_string()),
-            config_file: None,
-        };
+    fn test_require_in_sample_config() {
+        let config = Config::from_file("factorial.json").unwrap();
+        let converter = Converter::new(&config).unwrap();
+
+        // Positions start at 1, so the weights are 1!, 2!, 3!, ... and 1/2!,
+        // 1/3!, ... after the radix point.
+        assert_eq!(converter.convert("1000").unwrap(), "1000");
+        assert_eq!(converter.parse("111").unwrap(), BigInt::from(9));
+        assert_eq!(converter.convert("1.01").unwrap(), "1.01");
+    }
 
-        let config = Config::new(command_line_args);
-        let number = BigInt::from(3);
-        let config = &config.unwrap();
-        let converter = Converter::new(&config, number);
+    #[test]
+    fn test_convert_with_digits() {
+        let converter = Converter::new(&bases_config(10, 2)).unwrap();
+
+        let conversion = converter.convert_with_digits("-5.5").unwrap();
+
+        assert_eq!(conversion.output, "-101.1");
+        let digits = conversion.digits.as_deref().unwrap();
+        let weights: Vec<String> = digits.iter().map(|d| d.weight.to_string()).collect();
+        assert_eq!(weights, ["4", "2", "1", "1/2"]);
+        assert_eq!(
+            conversion.to_json()["digits"][3],
+            serde_json::json!({"position": -1, "symbol": "1", "value": 1, "weight": "1/2"})
+        );
+        assert_eq!(conversion.to_json()["number"], "-11/2");
+    }
 
-        assert_eq!(converter.calculate_base(lua_function, 3).unwrap(), BigInt::from(6));
+    #[test]
+    fn test_negative_weights_in_sample_config() {
+        let config = Config::from_file("negative.json").unwrap();
+        let converter = Converter::new(&config).unwrap();
+
+        // Both sides weigh -1 at position 0, so only the other sign needs a
+        // `-`. Hexadecimal digits over powers of -10 are not unique, and the
+        // smallest representation is written.
+        assert_eq!(converter.convert("1").unwrap(), "1");
+        assert_eq!(converter.convert("-1").unwrap(), "-1");
+        assert_eq!(converter.convert("10").unwrap(), "99999A");
     }
 }
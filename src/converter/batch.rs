@@ -0,0 +1,152 @@
+use super::Converter;
+use crate::error::{Error, Result};
+use std::io::{BufRead, Write};
+
+/// A line of a batch that could not be converted.
+#[derive(Debug)]
+pub struct LineError {
+    /// The line number, counted from 1.
+    pub line: usize,
+    pub input: String,
+    pub error: Error,
+}
+
+/// How many numbers of a batch were converted, and how many were not.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BatchSummary {
+    pub converted: usize,
+    pub failed: usize,
+}
+
+/// Whether a batch line holds no number: blank, or a comment starting with
+/// `#`.
+fn is_skipped(line: &str) -> bool {
+    line.is_empty() || line.starts_with('#')
+}
+
+impl Converter {
+    /// Converts the numbers of `input`, one per line with surrounding
+    /// whitespace ignored, and writes the results to `output` in the same
+    /// order. Blank lines and comments starting with `#` are skipped. Lines
+    /// that cannot be converted are passed to `failed`, which tells whether to
+    /// go on with the next line, and leave an empty line in the output so
+    /// that results stay paired with their numbers.
+    pub fn convert_lines(
+        &self,
+        input: impl BufRead,
+        mut output: impl Write,
+        mut failed: impl FnMut(LineError) -> bool,
+    ) -> Result<BatchSummary> {
+        let mut summary = BatchSummary::default();
+        for (index, line) in input.lines().enumerate() {
+            let line = line?;
+            let number = line.trim();
+            if is_skipped(number) {
+                continue;
+            }
+            match self.convert(number) {
+                Ok(converted) => {
+                    writeln!(output, "{}", converted)?;
+                    summary.converted += 1;
+                }
+                Err(error) => {
+                    summary.failed += 1;
+                    let keep_going = failed(LineError {
+                        line: index + 1,
+                        input: number.to_string(),
+                        error,
+                    });
+                    if !keep_going {
+                        break;
+                    }
+                    writeln!(output)?;
+                }
+            }
+        }
+        output.flush()?;
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{Config, System};
+    use crate::converter::Converter;
+
+    fn binary_to_hex() -> Converter {
+        Converter::new(&Config::new(System::constant(2), System::constant(16)).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_convert_lines() {
+        let input = "# ids\n1010\n\n  11111111  \n-1\n";
+        let mut output = Vec::new();
+
+        let summary = binary_to_hex()
+            .convert_lines(input.as_bytes(), &mut output, |_| true)
+            .unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), "A\nFF\n-1\n");
+        assert_eq!(summary.converted, 3);
+        assert_eq!(summary.failed, 0);
+    }
+
+    #[test]
+    fn test_invalid_lines() {
+        let input = "1\n12\n101\n2\n11\n";
+        let mut output = Vec::new();
+        let mut errors = Vec::new();
+
+        let summary = binary_to_hex()
+            .convert_lines(input.as_bytes(), &mut output, |error| {
+                errors.push((error.line, error.input, error.error.to_string()));
+                true
+            })
+            .unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), "1\n\n5\n\n3\n");
+        assert_eq!(summary.failed, 2);
+        assert_eq!(
+            errors[0],
+            (
+                2,
+                "12".to_string(),
+                "symbol '2' is not in the alphabet at position 1".to_string()
+            )
+        );
+        assert_eq!(errors[1].0, 4);
+    }
+
+    #[test]
+    fn test_results_stay_paired_with_lines() {
+        let input = "1010\n# ids\n1012\n\n1111\n";
+        let mut output = Vec::new();
+
+        binary_to_hex()
+            .convert_lines(input.as_bytes(), &mut output, |_| true)
+            .unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let numbers = ["1010", "1012", "1111"];
+        let pairs: Vec<(&str, &str)> = numbers.into_iter().zip(output.lines()).collect();
+        assert_eq!(pairs, [("1010", "A"), ("1012", ""), ("1111", "F")]);
+    }
+
+    #[test]
+    fn test_stop_at_first_invalid_line() {
+        let mut output = Vec::new();
+
+        let summary = binary_to_hex()
+            .convert_lines("1\n2\n11\n".as_bytes(), &mut output, |_| false)
+            .unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), "1\n");
+        assert_eq!(
+            summary,
+            super::BatchSummary {
+                converted: 1,
+                failed: 1
+            }
+        );
+    }
+}
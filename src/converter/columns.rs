@@ -0,0 +1,245 @@
+use super::{BatchSummary, Converter, LineError};
+use crate::error::{Error, Result};
+use csv::{ReaderBuilder, StringRecord, WriterBuilder};
+use std::{
+    convert::Infallible,
+    fmt::{self, Display, Formatter},
+    io::{Read, Write},
+    str::FromStr,
+};
+
+/// Suffix of the header of a column appended with the converted values.
+const APPENDED_SUFFIX: &str = "_converted";
+
+/// A column of a table, by its header or its index counted from 0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Column {
+    Name(String),
+    Index(usize),
+}
+
+/// Reads a column as on the command line: a number counted from 1, as with
+/// `cut`, or else a header.
+impl FromStr for Column {
+    type Err = Infallible;
+
+    fn from_str(column: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(match column.parse::<usize>() {
+            Ok(number) if number > 0 => Column::Index(number - 1),
+            _ => Column::Name(column.to_string()),
+        })
+    }
+}
+
+impl Display for Column {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Column::Name(name) => write!(f, "{:?}", name),
+            Column::Index(index) => write!(f, "{}", index + 1),
+        }
+    }
+}
+
+/// Which columns of a CSV or TSV table are converted, and how the table is
+/// laid out.
+#[derive(Debug, Clone)]
+pub struct Columns {
+    columns: Vec<Column>,
+    delimiter: u8,
+    headers: bool,
+    append: bool,
+}
+
+impl Columns {
+    /// Converts `columns` of a comma separated table with a header row, in
+    /// place.
+    pub fn new(columns: impl IntoIterator<Item = Column>) -> Self {
+        Self {
+            columns: columns.into_iter().collect(),
+            delimiter: b',',
+            headers: true,
+            append: false,
+        }
+    }
+
+    pub fn with_delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Whether the first row names the columns rather than holding numbers.
+    pub fn with_headers(mut self, headers: bool) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// Whether the converted values go into new columns after the others,
+    /// keeping the original ones, rather than replacing them.
+    pub fn with_append(mut self, append: bool) -> Self {
+        self.append = append;
+        self
+    }
+
+    /// The indices of the columns in a table with the first row `first`.
+    fn indices(&self, first: &StringRecord) -> Result<Vec<usize>> {
+        self.columns
+            .iter()
+            .map(|column| {
+                let index = match column {
+                    Column::Index(index) => Some(*index).filter(|&index| index < first.len()),
+                    Column::Name(name) if self.headers => first.iter().position(|h| h == name),
+                    Column::Name(_) => None,
+                };
+                index.ok_or_else(|| Error::UnknownColumn(column.clone()))
+            })
+            .collect()
+    }
+}
+
+impl Converter {
+    /// Converts the numbers in some columns of a CSV or TSV table, writing the
+    /// table with the other columns as they are to `output`. Surrounding
+    /// whitespace is ignored and empty cells stay empty. Cells that cannot be
+    /// converted are passed to `failed` with the line of their row, and are
+    /// left empty if it tells to go on.
+    pub fn convert_columns(
+        &self,
+        columns: &Columns,
+        input: impl Read,
+        output: impl Write,
+        mut failed: impl FnMut(LineError) -> bool,
+    ) -> Result<BatchSummary> {
+        let mut reader = ReaderBuilder::new()
+            .delimiter(columns.delimiter)
+            .has_headers(columns.headers)
+            .from_reader(input);
+        let mut writer = WriterBuilder::new()
+            .delimiter(columns.delimiter)
+            .from_writer(output);
+        let indices = columns.indices(reader.headers()?)?;
+        if columns.headers {
+            let mut headers = reader.headers()?.clone();
+            if columns.append {
+                for &index in &indices {
+                    headers.push_field(&format!("{}{}", &headers[index], APPENDED_SUFFIX));
+                }
+            }
+            writer.write_record(&headers)?;
+        }
+        let mut summary = BatchSummary::default();
+        for record in reader.records() {
+            let record = record?;
+            let mut fields: Vec<String> = record.iter().map(str::to_string).collect();
+            for &index in &indices {
+                let number = record[index].trim();
+                let converted = match number.is_empty() {
+                    true => String::new(),
+                    false => match self.convert(number) {
+                        Ok(converted) => {
+                            summary.converted += 1;
+                            converted
+                        }
+                        Err(error) => {
+                            summary.failed += 1;
+                            let keep_going = failed(LineError {
+                                line: record.position().map_or(0, |p| p.line() as usize),
+                                input: number.to_string(),
+                                error,
+                            });
+                            if !keep_going {
+                                writer.flush()?;
+                                return Ok(summary);
+                            }
+                            String::new()
+                        }
+                    },
+                };
+                match columns.append {
+                    true => fields.push(converted),
+                    false => fields[index] = converted,
+                }
+            }
+            writer.write_record(&fields)?;
+        }
+        writer.flush()?;
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, System};
+
+    fn hex_to_decimal() -> Converter {
+        Converter::new(&Config::new(System::constant(16), System::constant(10)).unwrap()).unwrap()
+    }
+
+    fn convert(columns: &Columns, input: &str) -> (String, Vec<(usize, String)>) {
+        let mut output = Vec::new();
+        let mut errors = Vec::new();
+        hex_to_decimal()
+            .convert_columns(columns, input.as_bytes(), &mut output, |error| {
+                errors.push((error.line, error.input));
+                true
+            })
+            .unwrap();
+        (String::from_utf8(output).unwrap(), errors)
+    }
+
+    #[test]
+    fn test_named_and_indexed_columns() {
+        let input = "id,name,parent\nFF,\"Smith, J\",10\n1A,Doe,\n";
+        let columns = Columns::new(["id".parse().unwrap(), "3".parse().unwrap()]);
+
+        let (output, errors) = convert(&columns, input);
+
+        assert_eq!(output, "id,name,parent\n255,\"Smith, J\",16\n26,Doe,\n");
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn test_append_to_tsv() {
+        let input = "FF\tx\nG\ty\n";
+        let columns = Columns::new([Column::Index(0)])
+            .with_delimiter(b'\t')
+            .with_headers(false)
+            .with_append(true);
+
+        let (output, errors) = convert(&columns, input);
+
+        assert_eq!(output, "FF\tx\t255\nG\ty\t\n");
+        assert_eq!(errors, vec![(2, "G".to_string())]);
+    }
+
+    #[test]
+    fn test_appended_headers() {
+        let columns = Columns::new([Column::Name("id".to_string())]).with_append(true);
+
+        let (output, _) = convert(&columns, "id\n10\n");
+
+        assert_eq!(output, "id,id_converted\n10,16\n");
+    }
+
+    #[test]
+    fn test_unknown_columns() {
+        for (columns, input) in [
+            (Columns::new([Column::Name("key".to_string())]), "id\n1\n"),
+            (Columns::new([Column::Index(1)]), "id\n1\n"),
+            (
+                Columns::new([Column::Name("id".to_string())]).with_headers(false),
+                "id\n1\n",
+            ),
+        ] {
+            let error = hex_to_decimal()
+                .convert_columns(&columns, input.as_bytes(), Vec::new(), |_| true)
+                .unwrap_err();
+
+            assert!(matches!(error, Error::UnknownColumn(_)), "{}", error);
+        }
+        assert_eq!(
+            Error::UnknownColumn("2".parse().unwrap()).to_string(),
+            "the table has no column 2"
+        );
+    }
+}
@@ -0,0 +1,313 @@
+use super::Converter;
+use crate::config::{Config, System};
+use crate::error::{ConfigError, Error, Result};
+use crate::system::Registry;
+use serde_json::{json, Map, Value};
+use std::{
+    collections::HashMap,
+    io::{BufRead, Write},
+};
+
+/// How many converters are kept for later requests with the same systems.
+const MAX_CACHED_CONVERTERS: usize = 64;
+
+/// Answers conversion requests given as JSON, one per line, for use as a
+/// long-running co-process. A request names the number and its systems:
+///
+/// ```json
+/// {"id": 1, "number": "1010", "source": {"base": 2}, "target": {"base": 16}}
+/// {"id": 2, "number": "FF", "config": "config.json"}
+/// ```
+///
+/// `source` and `target` are systems as in config files, or paths of config
+/// files whose source or target system is used. Constant systems without an
+/// alphabet get the default one of their base, as with `-s` and `-t`.
+/// `config` is a config file for both, which `source` and `target` override.
+/// Each response is a JSON line with the `id` of the request, if it has one,
+/// and the converted `result` or a structured `error`.
+#[derive(Debug, Default)]
+pub struct Service {
+    registry: Registry,
+    /// Converters built for earlier requests, by their config.
+    converters: HashMap<String, Converter>,
+}
+
+/// Why a request could not be answered, before any conversion.
+#[derive(Debug)]
+enum RequestError {
+    Invalid(String),
+    Failed(Error),
+}
+
+impl From<Error> for RequestError {
+    fn from(error: Error) -> Self {
+        RequestError::Failed(error)
+    }
+}
+
+impl From<ConfigError> for RequestError {
+    fn from(error: ConfigError) -> Self {
+        RequestError::Failed(error.into())
+    }
+}
+
+impl Service {
+    /// A service building systems with the kinds known to `registry`.
+    pub fn with_registry(registry: Registry) -> Self {
+        Self {
+            registry,
+            converters: HashMap::new(),
+        }
+    }
+
+    /// Answers the requests of `input` on `output`, one response line per
+    /// non-blank request line, flushed as soon as it is written. Returns how
+    /// many requests were answered.
+    pub fn serve(&mut self, input: impl BufRead, mut output: impl Write) -> Result<usize> {
+        let mut answered = 0;
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            writeln!(output, "{}", self.respond(&line))?;
+            output.flush()?;
+            answered += 1;
+        }
+        Ok(answered)
+    }
+
+    /// The response to a single request line.
+    pub fn respond(&mut self, line: &str) -> Value {
+        let (id, outcome) = match serde_json::from_str::<Value>(line) {
+            Ok(Value::Object(request)) => (request.get("id").cloned(), self.convert(&request)),
+            Ok(_) => (None, Err(invalid("request is not a JSON object"))),
+            Err(e) => (
+                None,
+                Err(invalid(format!("request is not valid JSON: {}", e))),
+            ),
+        };
+        let mut response = Map::new();
+        if let Some(id) = id {
+            response.insert("id".to_string(), id);
+        }
+        match outcome {
+            Ok(result) => response.insert("result".to_string(), Value::from(result)),
+            Err(RequestError::Invalid(message)) => response.insert(
+                "error".to_string(),
+                json!({"kind": "request", "message": message}),
+            ),
+            Err(RequestError::Failed(error)) => {
+                response.insert("error".to_string(), error_object(&error))
+            }
+        };
+        Value::Object(response)
+    }
+
+    fn convert(
+        &mut self,
+        request: &Map<String, Value>,
+    ) -> std::result::Result<String, RequestError> {
+        let number = match request.get("number") {
+            Some(Value::String(number)) => number,
+            Some(_) => return Err(invalid("number is not a string")),
+            None => return Err(invalid("request has no number")),
+        };
+        let config = self.config(request)?;
+        let key = serde_json::to_string(&config).unwrap_or_default();
+        if !self.converters.contains_key(&key) {
+            let converter = Converter::with_registry(&config, &self.registry)?;
+            if self.converters.len() >= MAX_CACHED_CONVERTERS {
+                self.converters.clear();
+            }
+            self.converters.insert(key.clone(), converter);
+        }
+        Ok(self.converters[&key].convert(number)?)
+    }
+
+    /// The config of the systems a request names.
+    fn config(&self, request: &Map<String, Value>) -> std::result::Result<Config, RequestError> {
+        let base = match request.get("config") {
+            None => None,
+            Some(Value::String(path)) => Some(Config::from_file_with(path, &self.registry)?),
+            Some(_) => return Err(invalid("config is not a path")),
+        };
+        let mut systems = Map::new();
+        for side in ["source", "target"] {
+            let system = match request.get(side) {
+                Some(Value::String(path)) => {
+                    let config = Config::from_file_with(path, &self.registry)?;
+                    system_value(&config, side)
+                }
+                Some(Value::Object(system)) => inline_system(system),
+                Some(_) => {
+                    let message = format!("{} is neither a system nor a config path", side);
+                    return Err(invalid(message));
+                }
+                None => match &base {
+                    Some(config) => system_value(config, side),
+                    None => {
+                        return Err(invalid(format!("request names no {} system", side)));
+                    }
+                },
+            };
+            systems.insert(side.to_string(), system);
+        }
+        let json = Value::Object(systems).to_string();
+        Ok(Config::from_json_with(&json, &self.registry)?)
+    }
+}
+
+/// A system given in a request, with the alphabet of its base filled in if
+/// it is a constant system without one, as with `-s` and `-t`.
+fn inline_system(system: &Map<String, Value>) -> Value {
+    let mut system = system.clone();
+    let constant = system.get("kind").is_none_or(|kind| kind == "constant");
+    let base = system
+        .get("base")
+        .and_then(Value::as_i64)
+        .and_then(|base| isize::try_from(base).ok());
+    if let (true, Some(base)) = (constant, base) {
+        if !["alphabet", "alphabets", "symbol_functions"]
+            .iter()
+            .any(|key| system.contains_key(*key))
+        {
+            let default =
+                serde_json::to_value(System::positional(Some(base), None)).unwrap_or_default();
+            system.insert("alphabet".to_string(), default["alphabet"].clone());
+        }
+    }
+    Value::Object(system)
+}
+
+fn system_value(config: &Config, side: &str) -> Value {
+    let system = match side {
+        "source" => config.get_source(),
+        _ => config.get_target(),
+    };
+    serde_json::to_value(system).unwrap_or_default()
+}
+
+fn invalid(message: impl Into<String>) -> RequestError {
+    RequestError::Invalid(message.into())
+}
+
+/// An error as a JSON object with its `kind`, its `message`, and where it is
+/// known the `position` of a parse error or the `diagnostics` of a config.
+fn error_object(error: &Error) -> Value {
+    let kind = match error {
+        Error::Config(_) => "config",
+        Error::Parse(_) => "parse",
+        Error::Lua(_) => "lua",
+        Error::Arithmetic(_) => "arithmetic",
+        Error::UnreadableInput { .. } | Error::Io(_) | Error::Csv(_) | Error::UnknownColumn(_) => {
+            "io"
+        }
+    };
+    let mut object = json!({"kind": kind, "message": error.to_string()});
+    match error {
+        Error::Parse(parse_error) => {
+            object["position"] = Value::from(parse_error.position);
+        }
+        Error::Config(ConfigError::Invalid(diagnostics)) => {
+            object["diagnostics"] = diagnostics
+                .iter()
+                .map(|d| json!({"path": d.path, "message": d.problem.to_string()}))
+                .collect();
+        }
+        _ => {}
+    }
+    object
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::System;
+
+    fn constant(base: isize) -> Value {
+        serde_json::to_value(System::constant(base)).unwrap()
+    }
+
+    #[test]
+    fn test_inline_systems() {
+        let mut service = Service::default();
+        let request =
+            json!({"id": 1, "number": "1010", "source": constant(2), "target": constant(16)});
+
+        let response = service.respond(&request.to_string());
+
+        assert_eq!(response, json!({"id": 1, "result": "A"}));
+    }
+
+    #[test]
+    fn test_documented_request() {
+        let mut service = Service::default();
+        let request =
+            r#"{"id": 1, "number": "1010", "source": {"base": 2}, "target": {"base": 16}}"#;
+        let mut output = Vec::new();
+
+        service.serve(request.as_bytes(), &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "{\"id\":1,\"result\":\"A\"}\n"
+        );
+    }
+
+    #[test]
+    fn test_config_paths() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/binary.json");
+        let mut service = Service::default();
+
+        let both = service.respond(&json!({"number": "LOLO", "config": path}).to_string());
+        let overridden = service.respond(
+            &json!({"number": "LOLO", "config": path, "target": constant(10)}).to_string(),
+        );
+        let sides = service
+            .respond(&json!({"number": "10", "source": constant(10), "target": path}).to_string());
+
+        assert_eq!(both, json!({"result": "A"}));
+        assert_eq!(overridden, json!({"result": "10"}));
+        assert_eq!(sides, json!({"result": "A"}));
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut service = Service::default();
+        let mut respond = |request: Value| service.respond(&request.to_string())["error"].clone();
+
+        let parse = respond(json!({"number": "12", "source": constant(2), "target": constant(10)}));
+        let config = respond(json!({"number": "1", "source": {"base": 1}, "target": {"base": 10}}));
+        let request = respond(json!({"id": "a", "number": "1", "source": constant(2)}));
+
+        assert_eq!(parse["kind"], "parse");
+        assert_eq!(parse["position"], 1);
+        assert_eq!(config["kind"], "config");
+        assert_eq!(config["diagnostics"][0]["path"], "$.source.base");
+        assert_eq!(
+            request,
+            json!({"kind": "request", "message": "request names no target system"})
+        );
+        assert_eq!(service.respond("[1]")["error"]["kind"], "request");
+    }
+
+    #[test]
+    fn test_serve() {
+        let request =
+            json!({"id": 1, "number": "7", "source": constant(10), "target": constant(2)});
+        let input = format!("{}\n\nnot json\n", request);
+        let mut output = Vec::new();
+
+        let answered = Service::default()
+            .serve(input.as_bytes(), &mut output)
+            .unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(answered, 2);
+        assert_eq!(lines[0], r#"{"id":1,"result":"111"}"#);
+        assert!(lines[1]
+            .starts_with(r#"{"error":{"kind":"request","message":"request is not valid JSON"#));
+    }
+}
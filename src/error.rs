@@ -0,0 +1,230 @@
+use crate::config::Diagnostic;
+use crate::converter::Column;
+use crate::system::Limit;
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use std::{
+    fmt::{self, Display, Formatter},
+    io,
+    path::PathBuf,
+};
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Every failure the converter can report, grouped by where it happened.
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+    #[error(transparent)]
+    Lua(#[from] LuaError),
+    #[error(transparent)]
+    Arithmetic(#[from] ArithmeticError),
+    #[error("cannot read {}: {source}", .path.display())]
+    UnreadableInput { path: PathBuf, source: io::Error },
+    #[error("input or output failed: {0}")]
+    Io(#[from] io::Error),
+    #[error("cannot read table: {0}")]
+    Csv(#[from] csv::Error),
+    #[error("the table has no column {0}")]
+    UnknownColumn(Column),
+}
+
+impl Error {
+    /// The process exit code the CLI uses for this class of error.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Error::Config(_) => 3,
+            Error::Parse(_) => 4,
+            Error::Lua(_) => 5,
+            Error::Arithmetic(_) => 6,
+            Error::UnreadableInput { .. }
+            | Error::Io(_)
+            | Error::Csv(_)
+            | Error::UnknownColumn(_) => 7,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum ConfigError {
+    #[error("invalid config:{}", DiagnosticList(.0))]
+    Invalid(Vec<Diagnostic>),
+    #[error("Both base and number cannot be negative")]
+    NegativeBaseAndNumber,
+    #[error("no numeral system of kind {0:?} is registered")]
+    UnknownKind(String),
+    #[error("invalid param {0:?}, expected source.NAME=VALUE or target.NAME=VALUE")]
+    InvalidParam(String),
+    #[error("cannot read weights file {}: {message}", .path.display())]
+    UnreadableWeights { path: PathBuf, message: String },
+    #[error("expression system has no expression")]
+    MissingExpression,
+    #[error(transparent)]
+    Expression(#[from] ExpressionError),
+    #[error("line {line} of weights file {} is not an integer or a fraction: {text:?}", .path.display())]
+    InvalidWeightLine {
+        path: PathBuf,
+        line: usize,
+        text: String,
+    },
+    #[error("weight {weight} of position {position} is not an integer, only positions after the radix point may have fractional weights")]
+    FractionalWeight {
+        position: usize,
+        weight: BigRational,
+    },
+    #[error("origin {origin} is outside the {positions} weights of the table")]
+    OriginOutsideTable { origin: isize, positions: usize },
+}
+
+/// A weight expression that could not be read. `column` counts characters
+/// from the start of `expression`.
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+#[error("invalid expression {expression:?}: {message} at column {column}")]
+pub struct ExpressionError {
+    pub expression: String,
+    pub column: usize,
+    pub message: String,
+}
+
+struct DiagnosticList<'a>(&'a [Diagnostic]);
+
+impl Display for DiagnosticList<'_> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        self.0.iter().try_for_each(|d| write!(f, "\n  {}", d))
+    }
+}
+
+/// A number that could not be read in its source system. `position` counts
+/// characters from the start of `input`.
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("{reason} at position {position}")]
+pub struct ParseError {
+    pub input: String,
+    pub position: usize,
+    pub reason: ParseErrorReason,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ParseErrorReason {
+    #[error("symbol {0:?} is not in the alphabet")]
+    UnknownSymbol(char),
+    #[error("symbol {0:?} is not in the alphabet")]
+    UnknownToken(String),
+    #[error("no symbol starts here")]
+    NoSymbol,
+    #[error("number is empty")]
+    Empty,
+    #[error("number has more digits than the {0} positions of the table")]
+    TooManyDigits(usize),
+    #[error("number has more digits after the radix point than the {0} positions there")]
+    TooManyFractionDigits(usize),
+    #[error("no digits follow the radix point")]
+    NoDigitsAfterPoint,
+    #[error("digit value {value} is outside {min}..={max}")]
+    DigitOutOfRange {
+        value: isize,
+        min: isize,
+        max: isize,
+    },
+}
+
+impl ParseError {
+    /// The input followed by a line marking the offending symbol.
+    pub fn underline(&self) -> String {
+        format!("{}\n{}^", self.input, " ".repeat(self.position))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum LuaError {
+    #[error("cannot load lua library: {0}")]
+    Library(#[source] mlua::Error),
+    #[error("cannot load lua function: {0}")]
+    Compile(#[source] mlua::Error),
+    #[error("lua function failed at position {position}: {source}")]
+    Call {
+        position: isize,
+        #[source]
+        source: mlua::Error,
+    },
+    #[error("lua function returned {value:?} at position {position}, expected {expected}")]
+    InvalidWeight {
+        position: isize,
+        value: String,
+        expected: &'static str,
+    },
+    #[error(
+        "lua function gave the empty digit range {min_digit}..={max_digit} at position {position}"
+    )]
+    EmptyDigitRange {
+        position: isize,
+        min_digit: isize,
+        max_digit: isize,
+    },
+    #[error("lua symbol function failed: {0}")]
+    Alphabet(#[source] mlua::Error),
+    #[error("lua symbol function returned {symbol:?} for digit value {value}, expected a non-empty string")]
+    InvalidSymbol { value: isize, symbol: String },
+    #[error("lua symbol functions do not read {symbol:?} back as digit value {value}")]
+    RoundTrip { value: isize, symbol: String },
+    #[error("lua system has no function")]
+    MissingFunction,
+    #[error("cannot read lua file {}: {source}", .path.display())]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    /// `position` is `None` if the limit was hit while loading the function.
+    #[error("lua function exceeded the {limit} {}", LimitedStep(.position))]
+    Limit {
+        position: Option<isize>,
+        limit: Limit,
+    },
+}
+
+struct LimitedStep<'a>(&'a Option<isize>);
+
+impl Display for LimitedStep<'_> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self.0 {
+            Some(position) => write!(f, "at position {}", position),
+            None => write!(f, "while loading"),
+        }
+    }
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ArithmeticError {
+    #[error("no digit of the alphabet is congruent to {residue} modulo {base}")]
+    NoDigit { residue: BigInt, base: isize },
+    #[error("{0} cannot be represented in this system")]
+    Unrepresentable(BigInt),
+    #[error("searching for the digits of {0} takes too long")]
+    SearchTooLarge(BigInt),
+    #[error("digit value {0} has no symbol in the alphabet")]
+    NoSymbol(isize),
+    #[error("weights must not shrink with the position, but position {0} does")]
+    DecreasingWeights(usize),
+    #[error("the system does not weight digits by position")]
+    NotPositional,
+    #[error("weight expression failed at position {position}: {message}")]
+    Expression { position: isize, message: String },
+    #[error("{number} needs more than the {positions} positions of the table")]
+    TableExhausted {
+        number: BigRational,
+        positions: usize,
+    },
+    #[error("{0} is not an integer")]
+    NotAnInteger(BigRational),
+    #[error(
+        "{number} cannot be written exactly with at most {digits} digits after the radix point"
+    )]
+    Inexact { number: BigRational, digits: usize },
+    #[error("the system writes `.` as a digit, so it has no radix point")]
+    NoRadixPoint,
+}
@@ -0,0 +1,330 @@
+use crate::converter::Converter;
+use crate::error::Result;
+use crate::system::{Digit, NumeralSystem};
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{One, Signed, Zero};
+use std::fmt::{self, Display, Formatter};
+
+/// How a number is read in the source system and written in the target
+/// system, with the weights the systems give their positions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Explanation {
+    pub source: Expansion,
+    pub number: BigRational,
+    pub target: Expansion,
+    /// How the digits of the target were found, most significant first for
+    /// subtractions and from the radix point out for a constant base.
+    pub steps: Vec<Step>,
+}
+
+/// A written number as the sum of its digits times their weights.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expansion {
+    pub system: String,
+    pub text: String,
+    /// Whether the digits sum to the negation of the number, written with a
+    /// `-`.
+    pub negative: bool,
+    /// `None` if the system does not write numbers digit by digit.
+    pub digits: Option<Vec<Digit>>,
+}
+
+/// A step of writing a number in the target system.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    /// `dividend = quotient·base + digit`, for a digit before the radix point
+    /// in a constant base.
+    Division {
+        dividend: BigInt,
+        base: BigInt,
+        quotient: BigInt,
+        digit: Digit,
+    },
+    /// `fraction·base = digit + rest`, for a digit after the radix point in a
+    /// constant base.
+    Multiplication {
+        fraction: BigRational,
+        base: BigInt,
+        digit: Digit,
+        rest: BigRational,
+    },
+    /// `remainder - digit·weight = rest`, for weights without a constant base.
+    /// The digits of such systems are found by a search, so these steps are
+    /// worked out backwards from the digits it found rather than taken from
+    /// the search itself.
+    Subtraction {
+        remainder: BigRational,
+        digit: Digit,
+        rest: BigRational,
+    },
+}
+
+/// Explains how `converter` converts `input`.
+pub fn explain(converter: &Converter, input: &str) -> Result<Explanation> {
+    let conversion = converter.convert_with_digits(input)?;
+    let source = expansion(converter.source(), input, &conversion.number)?;
+    let target = expansion(converter.target(), &conversion.output, &conversion.number)?;
+    let steps = match &target.digits {
+        None => Vec::new(),
+        // The steps find the digits of the number they sum to, which is the
+        // negation of the number converted if it is written with a `-`.
+        Some(digits) => match constant_base(converter.target(), digits)? {
+            Some(base) => base_steps(&base, digits),
+            None => subtraction_steps(digits),
+        },
+    };
+    Ok(Explanation {
+        source,
+        number: conversion.number,
+        target,
+        steps,
+    })
+}
+
+fn expansion(system: &dyn NumeralSystem, text: &str, number: &BigRational) -> Result<Expansion> {
+    let digits = system.digits(text)?;
+    let negative = digits
+        .as_deref()
+        .is_some_and(|digits| !number.is_zero() && digits_sum(digits) == -number);
+    Ok(Expansion {
+        system: system.describe(),
+        text: text.to_string(),
+        negative,
+        digits,
+    })
+}
+
+fn digits_sum<'a>(digits: impl IntoIterator<Item = &'a Digit>) -> BigRational {
+    digits
+        .into_iter()
+        .map(|digit| &digit.weight * BigInt::from(digit.value))
+        .sum()
+}
+
+/// The base `b` of a system whose position `k` weighs `b^k`, checked at the
+/// positions of `digits`.
+fn constant_base(system: &dyn NumeralSystem, digits: &[Digit]) -> Result<Option<BigInt>> {
+    let (Some(unit), Some(next)) = (system.place_at(0)?, system.place_at(1)?) else {
+        return Ok(None);
+    };
+    let base = next.weight;
+    if !unit.weight.is_one() || base.magnitude() < &2u32.into() {
+        return Ok(None);
+    }
+    let powers = digits.iter().all(|digit| {
+        i32::try_from(digit.position)
+            .is_ok_and(|position| digit.weight == BigRational::from(base.clone()).pow(position))
+    });
+    Ok(powers.then_some(base))
+}
+
+/// Divisions by `base` from the least significant digit before the radix
+/// point up, then multiplications by it from the point down.
+fn base_steps(base: &BigInt, digits: &[Digit]) -> Vec<Step> {
+    let (integer, fraction): (Vec<&Digit>, Vec<&Digit>) =
+        digits.iter().partition(|digit| digit.position >= 0);
+    let mut steps = Vec::with_capacity(digits.len());
+    let mut dividend = digits_sum(integer.iter().copied()).to_integer();
+    for digit in integer.into_iter().rev() {
+        let quotient = (&dividend - digit.value) / base;
+        steps.push(Step::Division {
+            dividend,
+            base: base.clone(),
+            quotient: quotient.clone(),
+            digit: digit.clone(),
+        });
+        dividend = quotient;
+    }
+    let mut rest = digits_sum(fraction.iter().copied());
+    for digit in fraction {
+        let fraction = rest;
+        rest = &fraction * base - BigInt::from(digit.value);
+        steps.push(Step::Multiplication {
+            fraction,
+            base: base.clone(),
+            digit: digit.clone(),
+            rest: rest.clone(),
+        });
+    }
+    steps
+}
+
+/// Subtractions of each digit times its weight, from the most significant
+/// digit down, starting from the sum of the final digits.
+fn subtraction_steps(digits: &[Digit]) -> Vec<Step> {
+    let mut remainder = digits_sum(digits);
+    digits
+        .iter()
+        .map(|digit| {
+            let rest = &remainder - &digit.weight * BigInt::from(digit.value);
+            Step::Subtraction {
+                remainder: std::mem::replace(&mut remainder, rest.clone()),
+                digit: digit.clone(),
+                rest,
+            }
+        })
+        .collect()
+}
+
+/// A weight or fraction as a factor, in parentheses unless it is a natural
+/// number.
+struct Factor<'a>(&'a BigRational);
+
+impl Display for Factor<'_> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self.0.is_integer() && !self.0.is_negative() {
+            true => write!(f, "{}", self.0),
+            false => write!(f, "({})", self.0),
+        }
+    }
+}
+
+impl Display for Expansion {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let Some(digits) = &self.digits else {
+            return write!(f, "{}", self.text);
+        };
+        let terms: Vec<String> = digits
+            .iter()
+            .map(|digit| format!("{}·{}", digit.value, Factor(&digit.weight)))
+            .collect();
+        let sum = match terms.is_empty() {
+            true => "0".to_string(),
+            false => terms.join(" + "),
+        };
+        match self.negative {
+            true => write!(f, "{} = -({})", self.text, sum),
+            false => write!(f, "{} = {}", self.text, sum),
+        }
+    }
+}
+
+impl Display for Step {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Step::Division {
+                dividend,
+                base,
+                quotient,
+                digit,
+            } => write!(
+                f,
+                "{} = {}·{} + {} → {}",
+                dividend,
+                quotient,
+                Factor(&base.clone().into()),
+                digit.value,
+                digit.symbol
+            ),
+            Step::Multiplication {
+                fraction,
+                base,
+                digit,
+                rest,
+            } => write!(
+                f,
+                "{}·{} = {} + {} → {}",
+                Factor(fraction),
+                Factor(&base.clone().into()),
+                digit.value,
+                Factor(rest),
+                digit.symbol
+            ),
+            Step::Subtraction {
+                remainder,
+                digit,
+                rest,
+            } => write!(
+                f,
+                "{} - {}·{} = {} → {}",
+                remainder,
+                digit.value,
+                Factor(&digit.weight),
+                rest,
+                digit.symbol
+            ),
+        }
+    }
+}
+
+impl Display for Explanation {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        writeln!(f, "source: {}", self.source.system)?;
+        writeln!(f, "  {} = {}", self.source, self.number)?;
+        writeln!(f, "target: {}", self.target.system)?;
+        for step in &self.steps {
+            writeln!(f, "  {}", step)?;
+        }
+        write!(f, "  {} = {}", self.target, self.number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, System};
+
+    fn explain_text(source: System, target: System, input: &str) -> String {
+        let converter = Converter::new(&Config::new(source, target).unwrap()).unwrap();
+        explain(&converter, input).unwrap().to_string()
+    }
+
+    #[test]
+    fn test_divisions() {
+        let text = explain_text(System::constant(2), System::constant(16), "11010");
+
+        assert_eq!(
+            text.lines().collect::<Vec<_>>()[1..],
+            [
+                "  11010 = 1·16 + 1·8 + 0·4 + 1·2 + 0·1 = 26",
+                "target: base 16 with digits 0123456789ABCDEF",
+                "  26 = 1·16 + 10 → A",
+                "  1 = 0·16 + 1 → 1",
+                "  1A = 1·16 + 10·1 = 26",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fractions_and_signs() {
+        let text = explain_text(System::constant(10), System::constant(2), "-2.25");
+
+        assert!(
+            text.contains("  -2.25 = -(2·1 + 2·(1/10) + 5·(1/100)) = -9/4"),
+            "{}",
+            text
+        );
+        assert!(text.contains("  2 = 1·2 + 0 → 0"), "{}", text);
+        assert!(text.contains("  (1/4)·2 = 0 + (1/2) → 0"), "{}", text);
+        assert!(text.contains("  (1/2)·2 = 1 + 0 → 1"), "{}", text);
+    }
+
+    #[test]
+    fn test_subtractions() {
+        let fibonacci =
+            System::expr("w(n-1) + w(n-2)", "01").with_weights([1, 2].map(BigInt::from));
+
+        let text = explain_text(System::constant(10), fibonacci, "9");
+
+        assert!(text.contains("  9 - 1·8 = 1 → 1"), "{}", text);
+        assert!(text.contains("  1 - 1·1 = 0 → 1"), "{}", text);
+        assert!(
+            text.ends_with("  10001 = 1·8 + 0·5 + 0·3 + 0·2 + 1·1 = 9"),
+            "{}",
+            text
+        );
+    }
+
+    #[test]
+    fn test_negative_base() {
+        let text = explain_text(System::constant(10), System::constant(-2), "-3");
+
+        assert!(text.contains("  -3 = 2·(-2) + 1 → 1"), "{}", text);
+        assert!(
+            text.ends_with("  1101 = 1·(-8) + 1·4 + 0·(-2) + 1·1 = -3"),
+            "{}",
+            text
+        );
+    }
+}
@@ -0,0 +1,46 @@
+//! Conversion of numbers between numeral systems.
+//!
+//! A [`System`] describes how digits are weighted by position, with a
+//! constant base, a table, an expression over the position or a lua function,
+//! and which symbol writes each digit value. A [`Config`] pairs a source and a target system, and a
+//! [`Converter`] parses numbers in one and renders them in the other. Numbers
+//! may have digits after a radix point, which are converted exactly.
+//!
+//! The `kind` of a system selects the [`NumeralSystem`] implementation that
+//! evaluates it. Further kinds can be added to a [`Registry`] and are then
+//! picked up from configs by name.
+//!
+//! Systems can be built in code:
+//!
+//! ```
+//! use numberbases::{Config, Converter, System};
+//!
+//! let config = Config::new(System::constant(2), System::constant(16)).unwrap();
+//! let converter = Converter::new(&config).unwrap();
+//!
+//! assert_eq!(converter.convert("11111111").unwrap(), "FF");
+//!
+//! let number = converter.parse("1010").unwrap();
+//! assert_eq!(converter.render(&number).unwrap(), "A");
+//! ```
+//!
+//! or loaded from JSON config files with [`Config::from_file`], which reports
+//! every problem in the file through [`ConfigError::Invalid`].
+
+pub mod analysis;
+pub mod config;
+pub mod consts;
+pub mod converter;
+pub mod error;
+pub mod explain;
+pub mod system;
+
+pub use config::{Config, Diagnostic, Problem, SymbolFunctions, System};
+pub use converter::{BatchSummary, Column, Columns, Conversion, Converter, LineError, Service};
+pub use error::{
+    ArithmeticError, ConfigError, Error, ExpressionError, LuaError, ParseError, ParseErrorReason,
+    Result,
+};
+pub use num_bigint::BigInt;
+pub use num_rational::BigRational;
+pub use system::{NumeralSystem, Registry};
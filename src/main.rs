@@ -1,14 +1,80 @@
-
-mod converter;
-mod config;
-mod consts;
-
-use clap::Parser;
-use std::io::Result;
-use crate::config::Config;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use numberbases::{
+    analysis::analyze, explain::explain, system::Sandbox, Column, Columns, Config, ConfigError,
+    Converter, Error, LineError, Service, System,
+};
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter},
+    process::ExitCode,
+};
 
 #[derive(Parser, Debug)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct CommandLineArgs {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    systems: SystemArgs,
+
+    /// number to convert, this is mandatory
+    #[clap(required_unless_present = "input")]
+    source_number: Option<String>,
+
+    /// Convert the numbers of a file, one per line, or of stdin if -
+    #[clap(
+        short = 'i',
+        long = "input",
+        value_name = "FILE",
+        conflicts_with = "source_number"
+    )]
+    input: Option<String>,
+
+    /// Report lines of the input that cannot be converted, write an empty line
+    /// for them and go on with the next
+    #[clap(long = "keep_going", conflicts_with = "source_number")]
+    keep_going: bool,
+
+    /// Read the input as a CSV table and convert this column, given by its
+    /// header or its number counted from 1
+    #[clap(
+        long = "column",
+        value_name = "COLUMN",
+        conflicts_with = "source_number"
+    )]
+    columns: Vec<Column>,
+
+    /// Read the table as tab separated values
+    #[clap(long = "tsv", requires = "columns", conflicts_with = "source_number")]
+    tsv: bool,
+
+    /// The table has no header row
+    #[clap(
+        long = "no_header",
+        requires = "columns",
+        conflicts_with = "source_number"
+    )]
+    no_header: bool,
+
+    /// Append the converted values as new columns instead of replacing the originals
+    #[clap(
+        long = "append",
+        requires = "columns",
+        conflicts_with = "source_number"
+    )]
+    append: bool,
+
+    /// Print the converted number, or a JSON object with the number in between
+    /// and the digits of the result with their values and weights
+    #[clap(long = "output", value_enum, default_value_t = OutputFormat::Text, conflicts_with = "input")]
+    output: OutputFormat,
+}
+
+// The source and target systems, as bases and alphabets or a config file. Not
+// a doc comment, as clap would show it as the about of the commands.
+#[derive(Args, Debug)]
+struct SystemArgs {
     /// Source base
     #[clap(short = 's', long = "source_base", conflicts_with_all = &["config_file", "source_alphabet"])]
     source_base: Option<isize>,
@@ -17,9 +83,6 @@ struct CommandLineArgs {
     #[clap(short = 't', long = "target_base", conflicts_with_all = &["config_file", "target_alphabet"])]
     target_base: Option<isize>,
 
-    /// number to convert, this is mandatory
-    source_number: String,
-
     /// Source alphabet as comma separated list
     #[clap(short = 'a', long = "source_alphabet", conflicts_with_all = &["config_file", "source_base"])]
     source_alphabet: Option<String>,
@@ -31,32 +94,460 @@ struct CommandLineArgs {
     /// Supply all arguments via a config file
     #[clap(short = 'c', long = "config_file", conflicts_with_all = &["source_base", "target_base", "source_alphabet", "target_alphabet"])]
     config_file: Option<String>,
+
+    /// Lua VM instructions a weight function may run per position, 0 for no limit
+    #[clap(long = "lua_instruction_limit")]
+    lua_instruction_limit: Option<u64>,
+
+    /// Bytes of memory a lua system may use, 0 for no limit
+    #[clap(long = "lua_memory_limit")]
+    lua_memory_limit: Option<usize>,
+
+    /// Milliseconds a weight function may run per position, 0 for no limit
+    #[clap(long = "lua_timeout_ms")]
+    lua_timeout_ms: Option<u64>,
+
+    /// Set a param of the source or target system, e.g. source.base=16
+    #[clap(short = 'p', long = "param", value_name = "SIDE.NAME=VALUE")]
+    params: Vec<String>,
 }
 
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    Text,
+    Json,
+}
 
-fn main() -> Result<()> {
-    let command_line_args = CommandLineArgs::parse();
-    println!("{:?}", command_line_args);
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Check config files and report every problem found
+    Validate {
+        /// Config files to check
+        #[clap(required = true)]
+        config_files: Vec<String>,
+    },
+    /// Check whether the systems of a config file write every number from 0 up
+    /// to a bound, and only in one way
+    CheckSystem {
+        /// Config file with the systems to check
+        config_file: String,
+
+        /// Largest number checked
+        #[clap(long = "bound", default_value_t = 1000)]
+        bound: u64,
+    },
+    /// Answer conversion requests read from stdin as JSON Lines, one JSON
+    /// response per request
+    Serve,
+    /// Explain step by step how a number is read in the source system and
+    /// how its digits in the target system are found
+    Explain {
+        #[command(flatten)]
+        systems: SystemArgs,
 
-    let config = Config::new(command_line_args);
-    println!("{:#?}", config);
-    let config = &config.unwrap();
-    let converter = converter::Converter::new(&config, 0.into());
+        /// Number to explain
+        number: String,
+    },
+}
 
-    println!("{:#?}", converter);
-   
-    // TODO: Use the source and target systems to convert positions
+/// Builds the config from a config file, or from the bases and alphabets given
+/// on the command line, then applies the params given on the command line.
+/// `number` is the number to convert, if there is a single one.
+fn config_from_args(systems: &SystemArgs, number: Option<&str>) -> Result<Config, ConfigError> {
+    let config = match &systems.config_file {
+        Some(config_file) => Config::from_file(config_file)?.map_systems(|system| {
+            let sandbox = sandbox_from_args(systems, system.get_sandbox());
+            system.with_sandbox(sandbox)
+        }),
+        None => positional_config(systems, number)?,
+    };
+    systems
+        .params
+        .iter()
+        .try_fold(config, |config, param| config.with_param_assignment(param))
+}
 
-    Ok(())
+/// Builds the config from the bases and alphabets given on the command line.
+fn positional_config(systems: &SystemArgs, number: Option<&str>) -> Result<Config, ConfigError> {
+    let source = System::positional(systems.source_base, systems.source_alphabet.as_deref());
+    let negative_number = number.is_some_and(|number| number.starts_with('-'));
+    if source.get_base() < 0 && negative_number {
+        return Err(ConfigError::NegativeBaseAndNumber);
+    }
+    let target = System::positional(systems.target_base, systems.target_alphabet.as_deref());
+    Config::new(source, target)
+}
+
+/// Overrides the limits of `sandbox` with those given on the command line.
+fn sandbox_from_args(systems: &SystemArgs, sandbox: Sandbox) -> Sandbox {
+    Sandbox {
+        instruction_limit: systems
+            .lua_instruction_limit
+            .unwrap_or(sandbox.instruction_limit),
+        memory_limit: systems.lua_memory_limit.unwrap_or(sandbox.memory_limit),
+        timeout_ms: systems.lua_timeout_ms.unwrap_or(sandbox.timeout_ms),
+    }
+}
+
+/// Builds the converter of the systems given on the command line.
+fn converter_from_args(systems: &SystemArgs, number: Option<&str>) -> Result<Converter, Error> {
+    config_from_args(systems, number)
+        .map_err(Error::from)
+        .and_then(|config| Converter::new(&config))
+}
+
+/// Checks each config file, then builds its systems to catch problems in lua
+/// code, such as symbol functions that do not read their symbols back.
+fn validate(config_files: &[String]) -> ExitCode {
+    let mut failure = None;
+    for config_file in config_files {
+        let built = Config::from_file(config_file)
+            .map_err(Error::from)
+            .and_then(|config| Converter::new(&config));
+        match built {
+            Ok(_) => println!("{}: ok", config_file),
+            Err(error) => {
+                match &error {
+                    Error::Config(ConfigError::Invalid(diagnostics)) => {
+                        for diagnostic in diagnostics {
+                            eprintln!("{}: {}", config_file, diagnostic);
+                        }
+                    }
+                    error => eprintln!("{}: {}", config_file, error),
+                }
+                failure = Some(error);
+            }
+        }
+    }
+    failure.map_or(ExitCode::SUCCESS, |error| ExitCode::from(error.exit_code()))
+}
+
+/// Analyses the source and target systems of a config file, failing unless
+/// both write every number up to `bound` exactly once.
+fn check_system(config_file: &str, bound: u64) -> ExitCode {
+    let converter = Config::from_file(config_file)
+        .map_err(Error::from)
+        .and_then(|config| Converter::new(&config));
+    let converter = match converter {
+        Ok(converter) => converter,
+        Err(error) => return report(&error),
+    };
+    let mut exact = true;
+    for (side, system) in [
+        ("source", converter.source()),
+        ("target", converter.target()),
+    ] {
+        let analysis = match analyze(system, bound) {
+            Ok(analysis) => analysis,
+            Err(error) => return report(&error),
+        };
+        println!("{}: {}", side, system.describe());
+        for line in analysis.to_string().lines() {
+            println!("  {}", line);
+        }
+        exact &= analysis.is_complete() && analysis.is_unique();
+    }
+    match exact {
+        true => ExitCode::SUCCESS,
+        false => ExitCode::FAILURE,
+    }
+}
+
+/// Converts the numbers of `input`, a file or `-` for stdin, one per line or in
+/// the columns of a table. Stops at the first number that cannot be converted
+/// unless `keep_going`.
+fn batch(converter: &Converter, input: &str, command_line_args: &CommandLineArgs) -> ExitCode {
+    let (name, reader): (&str, Box<dyn BufRead>) = match input {
+        "-" => ("<stdin>", Box::new(io::stdin().lock())),
+        path => match File::open(path) {
+            Ok(file) => (path, Box::new(BufReader::new(file))),
+            Err(source) => {
+                return report(&Error::UnreadableInput {
+                    path: path.into(),
+                    source,
+                })
+            }
+        },
+    };
+    let mut failure = None;
+    let failed = |failed: LineError| {
+        print_error(&format!("{}:{}", name, failed.line), &failed.error);
+        failure.get_or_insert(failed.error.exit_code());
+        command_line_args.keep_going
+    };
+    let output = BufWriter::new(io::stdout().lock());
+    let summary = match command_line_args.columns.as_slice() {
+        [] => converter.convert_lines(reader, output, failed),
+        columns => {
+            let columns = Columns::new(columns.iter().cloned())
+                .with_delimiter(if command_line_args.tsv { b'\t' } else { b',' })
+                .with_headers(!command_line_args.no_header)
+                .with_append(command_line_args.append);
+            converter.convert_columns(&columns, reader, output, failed)
+        }
+    };
+    match summary {
+        Ok(_) => failure.map_or(ExitCode::SUCCESS, ExitCode::from),
+        Err(error) => report(&error),
+    }
+}
+
+fn report(error: &Error) -> ExitCode {
+    print_error("error", error);
+    ExitCode::from(error.exit_code())
+}
+
+/// Prints `error` after `prefix`, underlining where a number could not be
+/// read.
+fn print_error(prefix: &str, error: &Error) {
+    eprintln!("{}: {}", prefix, error);
+    if let Error::Parse(parse_error) = error {
+        for line in parse_error.underline().lines() {
+            eprintln!("    {}", line);
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let command_line_args = CommandLineArgs::parse();
+    match &command_line_args.command {
+        Some(Command::Validate { config_files }) => return validate(config_files),
+        Some(Command::CheckSystem { config_file, bound }) => {
+            return check_system(config_file, *bound)
+        }
+        Some(Command::Serve) => {
+            let served = Service::default().serve(io::stdin().lock(), io::stdout().lock());
+            return match served {
+                Ok(_) => ExitCode::SUCCESS,
+                Err(error) => report(&error),
+            };
+        }
+        Some(Command::Explain { systems, number }) => {
+            let explained = converter_from_args(systems, Some(number))
+                .and_then(|converter| explain(&converter, number));
+            return match explained {
+                Ok(explanation) => {
+                    println!("{}", explanation);
+                    ExitCode::SUCCESS
+                }
+                Err(error) => report(&error),
+            };
+        }
+        None => {}
+    }
+    let converter = converter_from_args(
+        &command_line_args.systems,
+        command_line_args.source_number.as_deref(),
+    );
+    let converter = match converter {
+        Ok(converter) => converter,
+        Err(error) => return report(&error),
+    };
+    if let Some(input) = &command_line_args.input {
+        return batch(&converter, input, &command_line_args);
+    }
+
+    let source_number = command_line_args.source_number.clone().unwrap_or_default();
+    let printed = match command_line_args.output {
+        OutputFormat::Text => converter.convert(&source_number),
+        OutputFormat::Json => converter
+            .convert_with_digits(&source_number)
+            .map(|conversion| conversion.to_json().to_string()),
+    };
+    match printed {
+        Ok(printed) => {
+            println!("{}", printed);
+            ExitCode::SUCCESS
+        }
+        Err(error) => report(&error),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// The systems of a plain conversion, for tests to override.
+    fn systems() -> SystemArgs {
+        CommandLineArgs::parse_from(["numberbases", "0"]).systems
+    }
+
     #[test]
     fn verify_cli() {
         use clap::CommandFactory;
         CommandLineArgs::command().debug_assert()
     }
+
+    #[test]
+    fn test_config_new() {
+        let systems = SystemArgs {
+            source_base: Some(2),
+            target_base: Some(10),
+            source_alphabet: Some("01".to_string()),
+            target_alphabet: Some("0123456789".to_string()),
+            ..systems()
+        };
+
+        let config = config_from_args(&systems, Some("1010"));
+
+        assert_eq!(config.clone().unwrap().get_source().get_base(), 2);
+        assert_eq!(config.clone().unwrap().get_target().get_base(), 10);
+        assert_eq!(config.clone().unwrap().get_source().get_alphabet().len(), 2);
+        assert_eq!(
+            config.clone().unwrap().get_target().get_alphabet().len(),
+            10
+        );
+    }
+
+    #[test]
+    fn test_base_inferred_from_alphabet() {
+        let systems = SystemArgs {
+            source_alphabet: Some("01".to_string()),
+            target_alphabet: Some("01234567".to_string()),
+            ..systems()
+        };
+
+        let config = config_from_args(&systems, Some("1010"));
+
+        // The base should be inferred from the length of the alphabet
+        assert_eq!(config.clone().unwrap().get_source().get_base(), 2);
+        assert_eq!(config.clone().unwrap().get_target().get_base(), 8);
+    }
+
+    #[test]
+    fn test_base_defaults_to_10() {
+        let systems = SystemArgs { ..systems() };
+
+        let config = config_from_args(&systems, Some("1010"));
+
+        // The base should default to 10 if not provided
+        assert_eq!(config.clone().unwrap().get_source().get_base(), 10);
+        assert_eq!(config.clone().unwrap().get_target().get_base(), 10);
+    }
+
+    #[test]
+    fn test_negative_base() {
+        let systems = SystemArgs {
+            source_base: Some(-2),
+            target_base: Some(-10),
+            ..systems()
+        };
+
+        let config = config_from_args(&systems, Some("1101"));
+
+        // The base should be -2 and -10 as provided
+        assert_eq!(config.clone().unwrap().get_source().get_base(), -2);
+        assert_eq!(config.clone().unwrap().get_target().get_base(), -10);
+    }
+
+    #[test]
+    fn test_error_when_base_and_number_are_negative() {
+        let systems = SystemArgs {
+            source_base: Some(-2),
+            target_base: Some(10),
+            ..systems()
+        };
+
+        let config = config_from_args(&systems, Some("-1101"));
+
+        // An error should be returned when both base and number are negative
+        assert!(config.is_err());
+        assert_eq!(
+            config.unwrap_err().to_string(),
+            "Both base and number cannot be negative"
+        );
+    }
+
+    #[test]
+    fn test_degenerate_command_line_base() {
+        let systems = SystemArgs {
+            source_base: Some(1),
+            target_base: Some(10),
+            ..systems()
+        };
+
+        let config = config_from_args(&systems, Some("1"));
+
+        assert_eq!(
+            config.unwrap_err().to_string(),
+            "invalid config:\n  $.source.base: base 1 cannot represent every number"
+        );
+    }
+
+    #[test]
+    fn test_param_overrides_config_file() {
+        let command_line_args = CommandLineArgs::parse_from([
+            "numberbases",
+            "--config_file",
+            "config.json",
+            "--param",
+            "source.base=16",
+            "1",
+        ]);
+
+        let config = config_from_args(&command_line_args.systems, None).unwrap();
+
+        assert_eq!(config.get_source().get_params()["base"], 16);
+        assert_eq!(config.get_target().get_params()["base"], 2);
+    }
+
+    #[test]
+    fn test_batch_args() {
+        let command_line_args =
+            CommandLineArgs::parse_from(["numberbases", "-s", "2", "--input", "-", "--keep_going"]);
+
+        assert_eq!(command_line_args.input.as_deref(), Some("-"));
+        assert!(command_line_args.keep_going);
+        assert!(CommandLineArgs::try_parse_from(["numberbases", "--keep_going", "1"]).is_err());
+        assert!(CommandLineArgs::try_parse_from(["numberbases", "-i", "ids.txt", "1"]).is_err());
+    }
+
+    #[test]
+    fn test_column_args() {
+        let command_line_args = CommandLineArgs::parse_from([
+            "numberbases",
+            "-i",
+            "ids.tsv",
+            "--column",
+            "id",
+            "--column",
+            "3",
+            "--tsv",
+        ]);
+
+        assert_eq!(
+            command_line_args.columns,
+            [Column::Name("id".to_string()), Column::Index(2)]
+        );
+        assert!(command_line_args.tsv);
+        assert!(CommandLineArgs::try_parse_from(["numberbases", "--append", "-i", "-"]).is_err());
+    }
+
+    #[test]
+    fn test_output_format() {
+        let command_line_args =
+            CommandLineArgs::parse_from(["numberbases", "--output", "json", "1"]);
+
+        assert_eq!(command_line_args.output, OutputFormat::Json);
+        assert!(
+            CommandLineArgs::try_parse_from(["numberbases", "--output", "explain", "1"]).is_err()
+        );
+        assert!(
+            CommandLineArgs::try_parse_from(["numberbases", "--output", "json", "-i", "-"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_explain_args() {
+        let command_line_args =
+            CommandLineArgs::parse_from(["numberbases", "explain", "-s", "2", "-t", "16", "1010"]);
+
+        let Some(Command::Explain { systems, number }) = command_line_args.command else {
+            panic!("{:?}", command_line_args.command);
+        };
+        assert_eq!(number, "1010");
+        assert_eq!(systems.source_base, Some(2));
+        assert_eq!(systems.target_base, Some(16));
+    }
 }
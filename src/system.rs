@@ -0,0 +1,672 @@
+mod constant;
+mod decompose;
+mod expr;
+mod lua;
+mod table;
+
+use crate::config::System;
+use crate::consts::RADIX_POINT;
+use crate::error::{ArithmeticError, ConfigError, Error, ParseError, ParseErrorReason, Result};
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{Signed, Zero};
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Debug, Formatter},
+};
+
+pub use constant::ConstantSystem;
+pub use decompose::{places_within, Decomposer, Decomposition};
+pub use expr::{Expr, ExprSystem};
+pub use lua::{Limit, LuaSystem, Sandbox};
+pub use table::TableSystem;
+
+pub(crate) use decompose::{feasible_digits, sum_ranges};
+use lua::LuaAlphabet;
+
+/// A numeral system that numbers can be read from and written in.
+pub trait NumeralSystem: Debug {
+    /// Reads `input` as a number written in this system.
+    fn parse(&self, input: &str) -> Result<BigInt>;
+
+    /// Writes `number` in this system.
+    fn render(&self, number: &BigInt) -> Result<String>;
+
+    /// Reads `input`, which may have digits after a radix point.
+    fn parse_rational(&self, input: &str) -> Result<BigRational> {
+        self.parse(input).map(BigRational::from)
+    }
+
+    /// Writes `number`, with digits after a radix point for its fractional
+    /// part.
+    fn render_rational(&self, number: &BigRational) -> Result<String> {
+        match number.is_integer() {
+            true => self.render(&number.to_integer()),
+            false => Err(ArithmeticError::Inexact {
+                number: number.clone(),
+                digits: 0,
+            }
+            .into()),
+        }
+    }
+
+    /// A one-line, human readable summary of the system.
+    fn describe(&self) -> String;
+
+    /// The weight and digit range of `position`, counted from 0 at the least
+    /// significant digit. `None` past the last position of a system, and for
+    /// systems that do not weight digits by position.
+    fn place_at(&self, _position: usize) -> Result<Option<Place>> {
+        Ok(None)
+    }
+
+    /// The weight and digit range of `position` after the radix point,
+    /// counted from 1 next to the point. `None` past the last such position.
+    fn fraction_place_at(&self, _position: usize) -> Result<Option<FractionPlace>> {
+        Ok(None)
+    }
+
+    /// The digits of `input`, most significant first, with the weights of
+    /// their positions. `None` for systems that do not write numbers digit by
+    /// digit.
+    fn digits(&self, _input: &str) -> Result<Option<Vec<Digit>>> {
+        Ok(None)
+    }
+}
+
+/// Builds a [`NumeralSystem`] from its config.
+pub type Factory = Box<dyn Fn(&System) -> Result<Box<dyn NumeralSystem>>>;
+
+/// Maps the `kind` of a [`System`] to the factory that builds it. The default
+/// registry knows the built-in `constant`, `expr`, `lua` and `table` kinds.
+pub struct Registry {
+    factories: BTreeMap<String, Factory>,
+}
+
+impl Registry {
+    /// A registry without any kinds.
+    pub fn empty() -> Self {
+        Self {
+            factories: BTreeMap::new(),
+        }
+    }
+
+    /// Makes `kind` available to configs, replacing any earlier factory for it.
+    pub fn register<F>(&mut self, kind: impl Into<String>, factory: F)
+    where
+        F: Fn(&System) -> Result<Box<dyn NumeralSystem>> + 'static,
+    {
+        self.factories.insert(kind.into(), Box::new(factory));
+    }
+
+    /// The registered kinds in alphabetical order.
+    pub fn kinds(&self) -> Vec<&str> {
+        self.factories.keys().map(String::as_str).collect()
+    }
+
+    /// Builds `system` with the factory registered for its kind.
+    pub fn build(&self, system: &System) -> Result<Box<dyn NumeralSystem>> {
+        let kind = system.get_kind();
+        let factory = self
+            .factories
+            .get(kind)
+            .ok_or_else(|| ConfigError::UnknownKind(kind.to_string()))?;
+        factory(system)
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register("constant", |system| {
+            Ok(Box::new(ConstantSystem::new(system)?) as Box<dyn NumeralSystem>)
+        });
+        registry.register("expr", |system| {
+            Ok(Box::new(ExprSystem::new(system)?) as Box<dyn NumeralSystem>)
+        });
+        registry.register("lua", |system| {
+            Ok(Box::new(LuaSystem::new(system)?) as Box<dyn NumeralSystem>)
+        });
+        registry.register("table", |system| {
+            Ok(Box::new(TableSystem::new(system)?) as Box<dyn NumeralSystem>)
+        });
+        registry
+    }
+}
+
+impl Debug for Registry {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("Registry")
+            .field("kinds", &self.kinds())
+            .finish()
+    }
+}
+
+/// Upper bound on the positions tried when rendering with weights computed
+/// position by position, so weights that never grow cannot loop forever.
+const MAX_POSITIONS: usize = 4096;
+
+/// Upper bound on the digits written after the radix point, for numbers such
+/// as 1/3 that no number of digits writes exactly in base 10.
+const MAX_FRACTION_DIGITS: usize = 256;
+
+/// The weight of a position and the digit values allowed there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Place {
+    pub weight: BigInt,
+    pub min_digit: isize,
+    pub max_digit: isize,
+}
+
+/// The weight of a position after the radix point, an exact fraction such as
+/// `b^-k` or `1/k!`, and the digit values allowed there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FractionPlace {
+    pub weight: BigRational,
+    pub min_digit: isize,
+    pub max_digit: isize,
+}
+
+/// A digit of a written number: its position, negative after the radix
+/// point, its symbol and value, and the weight of the position.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Digit {
+    pub position: isize,
+    pub symbol: String,
+    pub value: isize,
+    pub weight: BigRational,
+}
+
+/// A number as digit values: its sign, the digits before the radix point,
+/// least significant first, and those after it, from the point on.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Numeral {
+    pub negative: bool,
+    pub integer: Vec<isize>,
+    pub fraction: Vec<isize>,
+}
+
+impl Numeral {
+    /// Where the digit at `position` was read, counting one character per
+    /// digit. Positions after the point are negative, from -1 on.
+    pub fn offset(&self, position: isize) -> usize {
+        let sign = usize::from(self.negative);
+        match usize::try_from(position) {
+            Ok(position) => sign + self.integer.len() - 1 - position,
+            Err(_) => sign + self.integer.len() + position.unsigned_abs(),
+        }
+    }
+}
+
+/// `number` as an integer, for reading numbers without a fractional part.
+fn integer(number: BigRational) -> Result<BigInt> {
+    match number.is_integer() {
+        true => Ok(number.to_integer()),
+        false => Err(ArithmeticError::NotAnInteger(number).into()),
+    }
+}
+
+/// Greedily decomposes `number` over the places `place(position)` gives, from
+/// the most significant position down, picking the largest digit each place
+/// allows. `place` returns `None` past the last position. Weights must not
+/// shrink with the position. Digits are least significant first.
+pub fn greedy_digits(
+    number: &BigInt,
+    mut place: impl FnMut(usize) -> Result<Option<Place>>,
+) -> Result<Vec<isize>> {
+    let mut places: Vec<Place> = Vec::new();
+    while !number.is_zero() {
+        let Some(next) = place(places.len())? else {
+            break;
+        };
+        if next.weight > *number {
+            break;
+        }
+        if places.last().is_some_and(|last| next.weight < last.weight) {
+            return Err(ArithmeticError::DecreasingWeights(places.len()).into());
+        }
+        places.push(next);
+    }
+
+    let unrepresentable = || ArithmeticError::Unrepresentable(number.clone());
+    let mut remainder = number.clone();
+    let mut digits = vec![0; places.len()];
+    for (position, place) in places.iter().enumerate().rev() {
+        // The largest digit of the range whose multiple fits.
+        let fits = match place.weight.is_zero() {
+            true => BigInt::zero(),
+            false => remainder.div_floor(&place.weight),
+        };
+        let digit = isize::try_from(fits.min(BigInt::from(place.max_digit)))
+            .ok()
+            .filter(|&digit| digit >= place.min_digit)
+            .ok_or_else(unrepresentable)?;
+        remainder -= &place.weight * digit;
+        digits[position] = digit;
+    }
+    if !remainder.is_zero() {
+        return Err(unrepresentable().into());
+    }
+    Ok(digits)
+}
+
+/// Decomposes `number` over the places `place(position)` gives, greedily or
+/// by searching for the representation `decomposition` prefers. Greedy
+/// decompositions are searched too where greedy digits could go wrong. Digits
+/// are least significant first.
+pub fn decompose_digits(
+    number: &BigInt,
+    decomposition: Decomposition,
+    place: impl FnMut(usize) -> Result<Option<Place>>,
+) -> Result<Vec<isize>> {
+    let places = places_within(number, place)?;
+    // Greedy digits are only right for weights from 0 up that do not shrink;
+    // others, such as negative or alternating weights, are searched.
+    let greedy_applies = !number.is_negative()
+        && places.iter().all(|place| !place.weight.is_negative())
+        && places
+            .windows(2)
+            .all(|pair| pair[0].weight <= pair[1].weight);
+    let decomposition = match decomposition {
+        Decomposition::Greedy if greedy_applies => {
+            return greedy_digits(number, |position| Ok(places.get(position).cloned()));
+        }
+        Decomposition::Greedy => Decomposition::Smallest,
+        decomposition => decomposition,
+    };
+    Decomposer::new(&places)
+        .find(number, decomposition)?
+        .ok_or_else(|| ArithmeticError::Unrepresentable(number.clone()).into())
+}
+
+/// Decomposes `number` like [`decompose_digits`], using the places after the
+/// radix point `fraction_place(position)` gives for a fractional part. Those
+/// positions are added one at a time, with every weight scaled to an integer,
+/// so that as few of them as possible are used.
+pub fn decompose_rational(
+    number: &BigRational,
+    decomposition: Decomposition,
+    mut place: impl FnMut(usize) -> Result<Option<Place>>,
+    mut fraction_place: impl FnMut(usize) -> Result<Option<FractionPlace>>,
+) -> Result<Numeral> {
+    if number.is_integer() {
+        let integer = decompose_digits(&number.to_integer(), decomposition, place)?;
+        return Ok(Numeral {
+            integer,
+            ..Numeral::default()
+        });
+    }
+    let mut fractions: Vec<FractionPlace> = Vec::new();
+    let mut denominator = number.denom().clone();
+    while fractions.len() < MAX_FRACTION_DIGITS {
+        let Some(next) = fraction_place(fractions.len() + 1)? else {
+            break;
+        };
+        denominator = denominator.lcm(next.weight.denom());
+        fractions.push(next);
+        let count = fractions.len();
+        let scaled = (number * &denominator).to_integer();
+        let digits = decompose_digits(&scaled, decomposition, |position| {
+            let Some(position) = position.checked_sub(count) else {
+                let fraction = &fractions[count - 1 - position];
+                return Ok(Some(Place {
+                    weight: (&fraction.weight * &denominator).to_integer(),
+                    min_digit: fraction.min_digit,
+                    max_digit: fraction.max_digit,
+                }));
+            };
+            Ok(place(position)?.map(|place| Place {
+                weight: place.weight * &denominator,
+                ..place
+            }))
+        });
+        match digits {
+            Ok(mut integer) => {
+                integer.resize(integer.len().max(count), 0);
+                let mut fraction: Vec<isize> = integer.drain(..count).rev().collect();
+                while fraction.last() == Some(&0) {
+                    fraction.pop();
+                }
+                return Ok(Numeral {
+                    negative: false,
+                    integer,
+                    fraction,
+                });
+            }
+            // More positions after the radix point only make the search
+            // larger.
+            Err(error @ Error::Arithmetic(ArithmeticError::SearchTooLarge(_))) => {
+                return Err(error)
+            }
+            Err(Error::Arithmetic(_)) => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Err(ArithmeticError::Inexact {
+        number: number.clone(),
+        digits: fractions.len(),
+    }
+    .into())
+}
+
+/// Writes digit values as symbols and reads them back, with the alphabets of
+/// a system or with its lua symbol functions. Positions count from 0 at the
+/// least significant digit.
+#[derive(Debug)]
+pub struct Symbols {
+    system: System,
+    functions: Option<LuaAlphabet>,
+}
+
+impl Symbols {
+    pub fn new(system: &System) -> Result<Self> {
+        let functions = match system.get_symbol_functions() {
+            Some(functions) => Some(LuaAlphabet::new(functions, system.get_sandbox())?),
+            None => None,
+        };
+        Ok(Self {
+            system: system.clone(),
+            functions,
+        })
+    }
+
+    /// The lowest and highest digit value at `position`. Alphabets have no
+    /// gaps, so every value in between is a digit.
+    pub fn digit_range(&self, position: isize) -> Option<(isize, isize)> {
+        if let Some(functions) = self.system.get_symbol_functions() {
+            return Some((functions.min_value, functions.max_value));
+        }
+        let values = self.system.get_alphabet_at(position).iter().map(|x| x.0);
+        Some((values.clone().min()?, values.max()?))
+    }
+
+    /// Whether every position has a negative digit, so that negative numbers
+    /// can be written without a sign.
+    pub fn has_negative_digits(&self) -> bool {
+        match self.system.get_symbol_functions() {
+            Some(functions) => functions.min_value < 0,
+            None => self
+                .system
+                .get_alphabets()
+                .all(|alphabet| alphabet.iter().any(|x| x.0 < 0)),
+        }
+    }
+
+    /// Splits `input` into digit values. A leading `-` is read as a sign
+    /// unless `-` is a symbol, and a `.` as the radix point unless `.` is one.
+    pub fn read_numeral(&self, input: &str) -> Result<Numeral> {
+        let (negative, letters) = match input.strip_prefix('-') {
+            Some(rest) if !self.is_symbol("-")? => (true, rest),
+            _ => (false, input),
+        };
+        if letters.is_empty() {
+            return Err(ParseError {
+                input: input.to_string(),
+                position: input.chars().count(),
+                reason: ParseErrorReason::Empty,
+            }
+            .into());
+        }
+        let offset = usize::from(negative);
+        let (integer, fraction) = match letters.split_once(RADIX_POINT) {
+            Some((integer, fraction)) if !self.is_symbol(&RADIX_POINT.to_string())? => {
+                (integer, Some(fraction))
+            }
+            _ => (letters, None),
+        };
+        let mut numeral = Numeral {
+            negative,
+            integer: self.read_part(input, integer, offset, |index, count| {
+                (count - 1 - index) as isize
+            })?,
+            fraction: Vec::new(),
+        };
+        numeral.integer.reverse();
+        if let Some(fraction) = fraction {
+            let offset = offset + integer.chars().count() + 1;
+            if fraction.is_empty() {
+                return Err(ParseError {
+                    input: input.to_string(),
+                    position: offset,
+                    reason: ParseErrorReason::NoDigitsAfterPoint,
+                }
+                .into());
+            }
+            numeral.fraction =
+                self.read_part(input, fraction, offset, |index, _| -1 - index as isize)?;
+        }
+        Ok(numeral)
+    }
+
+    /// The digits of `input`, most significant first, with the weights
+    /// `system` gives their positions.
+    pub fn weighed_digits(&self, input: &str, system: &dyn NumeralSystem) -> Result<Vec<Digit>> {
+        let numeral = self.read_numeral(input)?;
+        let integer = (numeral.integer.iter().enumerate().rev())
+            .map(|(position, &value)| (position as isize, value));
+        let fraction = (numeral.fraction.iter().enumerate())
+            .map(|(index, &value)| (-1 - index as isize, value));
+        integer
+            .chain(fraction)
+            .map(|(position, value)| {
+                let weight = match usize::try_from(position) {
+                    Ok(position) => system
+                        .place_at(position)?
+                        .map(|place| BigRational::from(place.weight)),
+                    Err(_) => system
+                        .fraction_place_at(position.unsigned_abs())?
+                        .map(|place| place.weight),
+                };
+                Ok(Digit {
+                    position,
+                    symbol: self.symbol(position, value)?,
+                    value,
+                    weight: weight.ok_or(ArithmeticError::NotPositional)?,
+                })
+            })
+            .collect()
+    }
+
+    /// The digit values of `letters`, most significant first, where `letters`
+    /// start at character `offset` of `input`. The `index`th of `count` digits
+    /// is at `position(index, count)`.
+    fn read_part(
+        &self,
+        input: &str,
+        letters: &str,
+        offset: usize,
+        position: impl Fn(usize, usize) -> isize,
+    ) -> Result<Vec<isize>> {
+        let error = |position, reason| ParseError {
+            input: input.to_string(),
+            position,
+            reason,
+        };
+        let (tokens, rest) = match &self.functions {
+            Some(functions) => functions.split(letters)?,
+            None => (
+                letters.chars().map(String::from).enumerate().collect(),
+                None,
+            ),
+        };
+        if let Some(index) = rest {
+            return Err(error(offset + index, ParseErrorReason::NoSymbol).into());
+        }
+        let count = tokens.len();
+        let mut values = Vec::with_capacity(count);
+        for (index, (start, token)) in tokens.into_iter().enumerate() {
+            let value = self
+                .value(position(index, count), &token)?
+                .map_err(|reason| error(offset + start, reason))?;
+            values.push(value);
+        }
+        Ok(values)
+    }
+
+    /// Writes `number` with the digits `digits` finds for it. A number they
+    /// cannot represent is written as `-` and the digits of its negation if
+    /// those exist, so that a sign is only written where the system needs one.
+    pub fn write_number(
+        &self,
+        number: &BigRational,
+        digits: impl Fn(&BigRational) -> Result<Numeral>,
+    ) -> Result<String> {
+        match digits(number) {
+            Ok(numeral) => self.write_numeral(&numeral),
+            Err(Error::Arithmetic(error)) if !number.is_zero() => match digits(&-number) {
+                Ok(numeral) => self.write_numeral(&Numeral {
+                    negative: true,
+                    ..numeral
+                }),
+                Err(_) => Err(error.into()),
+            },
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Writes `numeral`. No digits before the point are written as the zero
+    /// digit.
+    pub fn write_numeral(&self, numeral: &Numeral) -> Result<String> {
+        let mut text = String::new();
+        if numeral.negative {
+            text.push('-');
+        }
+        if numeral.integer.is_empty() {
+            text += &self.symbol(0, 0)?;
+        }
+        for (position, &value) in numeral.integer.iter().enumerate().rev() {
+            text += &self.symbol(position as isize, value)?;
+        }
+        if !numeral.fraction.is_empty() {
+            if self.is_symbol(&RADIX_POINT.to_string())? {
+                return Err(ArithmeticError::NoRadixPoint.into());
+            }
+            text.push(RADIX_POINT);
+            for (index, &value) in numeral.fraction.iter().enumerate() {
+                text += &self.symbol(-1 - index as isize, value)?;
+            }
+        }
+        Ok(text)
+    }
+
+    /// The symbol of digit `value` at `position`.
+    pub fn symbol(&self, position: isize, value: isize) -> Result<String> {
+        match &self.functions {
+            Some(functions) => {
+                let (min, max) = self.digit_range(position).unwrap_or_default();
+                if !(min..=max).contains(&value) {
+                    return Err(ArithmeticError::NoSymbol(value).into());
+                }
+                Ok(functions.symbol(value)?)
+            }
+            None => Ok(self
+                .system
+                .get_alphabet_letter_by_value(position, value)?
+                .to_string()),
+        }
+    }
+
+    /// The digit value `token` stands for at `position`.
+    fn value(
+        &self,
+        position: isize,
+        token: &str,
+    ) -> Result<std::result::Result<isize, ParseErrorReason>> {
+        let Some(functions) = &self.functions else {
+            let mut letters = token.chars();
+            return Ok(match (letters.next(), letters.next()) {
+                (Some(letter), None) => self.system.get_value_by_alphabet_letter(position, letter),
+                _ => Err(ParseErrorReason::UnknownToken(token.to_string())),
+            });
+        };
+        let (min, max) = self.digit_range(position).unwrap_or_default();
+        Ok(functions
+            .value(token)?
+            .filter(|value| (min..=max).contains(value))
+            .ok_or_else(|| ParseErrorReason::UnknownToken(token.to_string())))
+    }
+
+    fn is_symbol(&self, text: &str) -> Result<bool> {
+        match &self.functions {
+            Some(functions) => Ok(functions.value(text)?.is_some()),
+            None => Ok(text.chars().all(|letter| self.system.has_symbol(letter))),
+        }
+    }
+
+    /// The symbols of each alphabet in order of their digit values, separated
+    /// by `/`, or the range of digits the lua symbol functions write.
+    pub fn describe(&self) -> String {
+        if let Some(functions) = self.system.get_symbol_functions() {
+            return format!(
+                "{}..={} written by lua",
+                functions.min_value, functions.max_value
+            );
+        }
+        let symbols: Vec<String> = self
+            .system
+            .get_alphabets()
+            .map(|alphabet| {
+                let mut alphabet = alphabet.clone();
+                alphabet.sort_unstable();
+                alphabet.into_iter().map(|x| x.1).collect()
+            })
+            .collect();
+        symbols.join("/")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+
+    #[derive(Debug)]
+    struct Tally;
+
+    impl NumeralSystem for Tally {
+        fn parse(&self, input: &str) -> Result<BigInt> {
+            Ok(BigInt::from(input.chars().count()))
+        }
+
+        fn render(&self, number: &BigInt) -> Result<String> {
+            Ok("|".repeat(number.try_into().unwrap_or_default()))
+        }
+
+        fn describe(&self) -> String {
+            "tally marks".to_string()
+        }
+    }
+
+    #[test]
+    fn test_default_kinds() {
+        assert_eq!(
+            Registry::default().kinds(),
+            vec!["constant", "expr", "lua", "table"]
+        );
+    }
+
+    #[test]
+    fn test_register_kind() {
+        let mut registry = Registry::default();
+        registry.register("tally", |_| Ok(Box::new(Tally) as Box<dyn NumeralSystem>));
+        let system = System::constant(2).with_kind("tally");
+
+        let tally = registry.build(&system).unwrap();
+
+        assert_eq!(tally.render(&BigInt::from(3)).unwrap(), "|||");
+        assert_eq!(tally.describe(), "tally marks");
+    }
+
+    #[test]
+    fn test_unknown_kind() {
+        let system = System::constant(2).with_kind("tally");
+
+        let error = Registry::default().build(&system).unwrap_err();
+
+        assert!(matches!(
+            error,
+            Error::Config(ConfigError::UnknownKind(kind)) if kind == "tally"
+        ));
+    }
+}
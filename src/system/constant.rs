@@ -0,0 +1,283 @@
+use super::{
+    integer, Digit, FractionPlace, Numeral, NumeralSystem, Place, Symbols, MAX_FRACTION_DIGITS,
+};
+use crate::config::System;
+use crate::error::{ArithmeticError, Result};
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{Signed, Zero};
+
+/// A positional system where the weight of position `n` is `base^n`, and
+/// `base^-k` at the `k`th position after the radix point. Positions count
+/// from the radix point, so the origin does not change the weights.
+#[derive(Debug)]
+pub struct ConstantSystem {
+    system: System,
+    symbols: Symbols,
+}
+
+impl ConstantSystem {
+    pub fn new(system: &System) -> Result<Self> {
+        Ok(Self {
+            system: system.clone(),
+            symbols: Symbols::new(system)?,
+        })
+    }
+
+    /// Whether negative numbers can be written without a sign, as negative
+    /// bases and alphabets with negative digits can.
+    fn represents_negatives(&self) -> bool {
+        self.system.get_base() < 0 || self.symbols.has_negative_digits()
+    }
+
+    /// Scales `number` by the base until it is an integer and writes that,
+    /// moving the radix point back by as many positions.
+    fn numeral(&self, number: &BigRational) -> std::result::Result<Numeral, ArithmeticError> {
+        let base = BigInt::from(self.system.get_base());
+        let mut scaled = number.clone();
+        let mut count = 0;
+        while !scaled.is_integer() {
+            if count == MAX_FRACTION_DIGITS {
+                return Err(ArithmeticError::Inexact {
+                    number: number.clone(),
+                    digits: count,
+                });
+            }
+            scaled *= &base;
+            count += 1;
+        }
+        let mut integer = self.digits(&scaled.to_integer(), count)?;
+        integer.resize(integer.len().max(count), 0);
+        let fraction = integer.drain(..count).rev().collect();
+        Ok(Numeral {
+            negative: false,
+            integer,
+            fraction,
+        })
+    }
+
+    /// Repeatedly picks the digit congruent to `number` modulo the base and
+    /// divides it out, which works for negative bases and for digit sets other
+    /// than `0..base`. Digits are least significant first, the first `shift`
+    /// of them after the radix point.
+    fn digits(
+        &self,
+        number: &BigInt,
+        shift: usize,
+    ) -> std::result::Result<Vec<isize>, ArithmeticError> {
+        let base = self.system.get_base();
+        let big_base = BigInt::from(base);
+        let modulus = BigInt::from(base.unsigned_abs());
+        // With any finite digit set the number shrinks by the base on each step
+        // until it either reaches zero or cycles.
+        let limit = number.bits() as usize + 128;
+        let mut remaining = number.clone();
+        let mut digits = Vec::new();
+        while !remaining.is_zero() {
+            if digits.len() > limit {
+                return Err(ArithmeticError::Unrepresentable(number.clone()));
+            }
+            let residue = remaining.mod_floor(&modulus);
+            let no_digit = || ArithmeticError::NoDigit {
+                residue: residue.clone(),
+                base,
+            };
+            let (min, max) = self
+                .symbols
+                .digit_range(digits.len() as isize - shift as isize)
+                .ok_or_else(no_digit)?;
+            // The smallest digit of the range congruent to the residue.
+            let digit = (&residue - min).mod_floor(&modulus) + min;
+            let digit = isize::try_from(digit)
+                .ok()
+                .filter(|&digit| digit <= max)
+                .ok_or_else(no_digit)?;
+            remaining = (remaining - digit) / &big_base;
+            digits.push(digit);
+        }
+        Ok(digits)
+    }
+}
+
+impl NumeralSystem for ConstantSystem {
+    fn parse(&self, input: &str) -> Result<BigInt> {
+        integer(self.parse_rational(input)?)
+    }
+
+    fn render(&self, number: &BigInt) -> Result<String> {
+        self.render_rational(&BigRational::from(number.clone()))
+    }
+
+    fn parse_rational(&self, input: &str) -> Result<BigRational> {
+        let numeral = self.symbols.read_numeral(input)?;
+        let base = BigInt::from(self.system.get_base());
+        let digits = numeral.integer.iter().rev().chain(&numeral.fraction);
+        let number = digits.fold(BigInt::zero(), |number, &value| number * &base + value);
+        let scale = num_traits::pow(base, numeral.fraction.len());
+        let number = BigRational::new(number, scale);
+        Ok(if numeral.negative { -number } else { number })
+    }
+
+    /// Falls back to a leading `-` when the system cannot represent a negative
+    /// number on its own.
+    fn render_rational(&self, number: &BigRational) -> Result<String> {
+        if number.is_negative() && !self.represents_negatives() {
+            return Ok(format!("-{}", self.render_rational(&-number)?));
+        }
+        self.symbols.write_numeral(&self.numeral(number)?)
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "base {} with digits {}",
+            self.system.get_base(),
+            self.symbols.describe()
+        )
+    }
+
+    fn place_at(&self, position: usize) -> Result<Option<Place>> {
+        let Some((min_digit, max_digit)) = self.symbols.digit_range(position as isize) else {
+            return Ok(None);
+        };
+        let weight = num_traits::pow(BigInt::from(self.system.get_base()), position);
+        Ok(Some(Place {
+            weight,
+            min_digit,
+            max_digit,
+        }))
+    }
+
+    fn fraction_place_at(&self, position: usize) -> Result<Option<FractionPlace>> {
+        let Some((min_digit, max_digit)) = self.symbols.digit_range(-(position as isize)) else {
+            return Ok(None);
+        };
+        let power = num_traits::pow(BigInt::from(self.system.get_base()), position);
+        Ok(Some(FractionPlace {
+            weight: BigRational::new(1.into(), power),
+            min_digit,
+            max_digit,
+        }))
+    }
+
+    fn digits(&self, input: &str) -> Result<Option<Vec<Digit>>> {
+        self.symbols.weighed_digits(input, self).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SymbolFunctions;
+    use crate::error::{Error, ParseErrorReason};
+
+    #[test]
+    fn test_balanced_ternary() {
+        let system = System::constant(3).with_alphabet([(-1, 'T'), (0, '0'), (1, '1')]);
+        let ternary = ConstantSystem::new(&system).unwrap();
+
+        assert_eq!(ternary.render(&BigInt::from(-5)).unwrap(), "T11");
+        assert_eq!(ternary.parse("1TT").unwrap(), BigInt::from(5));
+    }
+
+    #[test]
+    fn test_counting_rods() {
+        // Units are written with vertical rods, tens with horizontal ones.
+        let rods = |first: u32| -> Vec<(isize, char)> {
+            let digits = (0..9).map(|d| char::from_u32(first + d).unwrap());
+            std::iter::once('〇')
+                .chain(digits)
+                .enumerate()
+                .map(|(value, symbol)| (value as isize, symbol))
+                .collect()
+        };
+        let system = System::constant(10).with_alphabets([rods(0x1D360), rods(0x1D369)], true);
+        let counting_rods = ConstantSystem::new(&system).unwrap();
+
+        assert_eq!(
+            counting_rods.render(&BigInt::from(1234)).unwrap(),
+            "\u{1D369}\u{1D361}\u{1D36B}\u{1D363}"
+        );
+        assert_eq!(
+            counting_rods
+                .parse("\u{1D369}\u{1D361}\u{1D36B}\u{1D363}")
+                .unwrap(),
+            BigInt::from(1234)
+        );
+        assert!(counting_rods
+            .parse("\u{1D360}\u{1D361}\u{1D362}\u{1D363}")
+            .is_err());
+    }
+
+    #[test]
+    fn test_symbol_functions() {
+        let system = System::constant(1000).with_symbol_functions(SymbolFunctions {
+            symbol: "return function (v) return '<' .. v .. '>' end".to_string(),
+            value: "return function (s) return tonumber(s:match('^<(%d+)>$')) end".to_string(),
+            pattern: Some("<%d+>".to_string()),
+            min_value: 0,
+            max_value: 999,
+        });
+        let thousands = ConstantSystem::new(&system).unwrap();
+
+        assert_eq!(
+            thousands.render(&BigInt::from(1_002_345)).unwrap(),
+            "<1><2><345>"
+        );
+        assert_eq!(
+            thousands.parse("<1><0><7>").unwrap(),
+            BigInt::from(1_000_007)
+        );
+        assert_eq!(
+            thousands.describe(),
+            "base 1000 with digits 0..=999 written by lua"
+        );
+        let Err(Error::Parse(error)) = thousands.parse("<1><1000>") else {
+            panic!("expected <1000> to be rejected");
+        };
+        assert_eq!(error.position, 3);
+        assert_eq!(
+            error.reason,
+            ParseErrorReason::UnknownToken("<1000>".to_string())
+        );
+    }
+
+    #[test]
+    fn test_radix_point() {
+        let decimal = ConstantSystem::new(&System::constant(10)).unwrap();
+        let binary = ConstantSystem::new(&System::constant(2)).unwrap();
+        let negabinary = ConstantSystem::new(&System::constant(-2)).unwrap();
+        let number = decimal.parse_rational("-12.375").unwrap();
+
+        assert_eq!(number, BigRational::new((-99).into(), 8.into()));
+        assert_eq!(binary.render_rational(&number).unwrap(), "-1100.011");
+        assert_eq!(
+            negabinary
+                .render_rational(&BigRational::new(1.into(), 2.into()))
+                .unwrap(),
+            "1.1"
+        );
+        assert!(matches!(
+            binary.render_rational(&BigRational::new(1.into(), 3.into())),
+            Err(Error::Arithmetic(ArithmeticError::Inexact {
+                digits: 256,
+                ..
+            }))
+        ));
+        assert!(matches!(
+            decimal.parse("1.5"),
+            Err(Error::Arithmetic(ArithmeticError::NotAnInteger(_)))
+        ));
+        let Err(Error::Parse(error)) = decimal.parse_rational("1.") else {
+            panic!("expected a missing fraction to be rejected");
+        };
+        assert_eq!(error.reason, ParseErrorReason::NoDigitsAfterPoint);
+    }
+
+    #[test]
+    fn test_describe() {
+        let binary = ConstantSystem::new(&System::constant(2)).unwrap();
+
+        assert_eq!(binary.describe(), "base 2 with digits 01");
+    }
+}
@@ -0,0 +1,389 @@
+use super::{Place, MAX_POSITIONS};
+use crate::error::{ArithmeticError, Result};
+use num_bigint::{BigInt, Sign};
+use num_integer::Integer;
+use num_traits::{Signed, ToPrimitive, Zero};
+use serde::{Deserialize, Serialize};
+use std::{cell::Cell, ops::ControlFlow};
+
+/// How many partial representations a search may visit before giving up.
+const MAX_SEARCH_NODES: usize = 1_000_000;
+
+/// How a system picks the digits of a number when writing it.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Decomposition {
+    /// The largest digit that fits at each position from the most significant
+    /// down. Fast, but fails for weights that do not divide each other.
+    #[default]
+    Greedy,
+    /// The representation with the smallest digits, compared from the most
+    /// significant position down.
+    Smallest,
+    /// The representation with the fewest non-zero digits, the smallest of
+    /// those if there are several.
+    FewestNonzero,
+}
+
+/// The places of the positions whose weights are at most `bound` in
+/// magnitude, and those above while the positions below them can cancel a
+/// digit of their weight down to a number within the bound, as negative
+/// weights and digits allow. Weights are assumed to keep growing past the
+/// first position that cannot be cancelled, so the later ones cannot be
+/// either. Stops at the last position `place` gives.
+pub fn places_within(
+    bound: &BigInt,
+    mut place: impl FnMut(usize) -> Result<Option<Place>>,
+) -> Result<Vec<Place>> {
+    let bound = bound.abs();
+    let mut places = Vec::new();
+    let (mut low, mut high) = (BigInt::zero(), BigInt::zero());
+    while places.len() < MAX_POSITIONS {
+        let Some(next) = place(places.len())? else {
+            break;
+        };
+        if next.weight.magnitude() > bound.magnitude() {
+            let cancelled = feasible_digits(&next, &(&bound - &low), &(-&bound - &high));
+            if matches!(cancelled, None | Some((0, 0))) {
+                break;
+            }
+        }
+        let (min, max) = sum_range(&next);
+        low += min;
+        high += max;
+        places.push(next);
+    }
+    Ok(places)
+}
+
+/// Every way of writing numbers with the digits of some places, found by a
+/// depth-first search from the most significant position down that drops
+/// partial sums the lower positions cannot complete.
+#[derive(Debug)]
+pub struct Decomposer<'a> {
+    places: &'a [Place],
+    /// The lowest and highest sums the positions below each position reach.
+    low: Vec<BigInt>,
+    high: Vec<BigInt>,
+}
+
+impl<'a> Decomposer<'a> {
+    pub fn new(places: &'a [Place]) -> Self {
+        let (low, high) = sum_ranges(places);
+        Self { places, low, high }
+    }
+
+    /// The representation of `number` in the order `decomposition` prefers,
+    /// least significant digit first and without leading zeros. Greedy
+    /// decompositions are left to [`super::greedy_digits`] and are searched
+    /// like [`Decomposition::Smallest`] here. Fails if the search visits more
+    /// than [`MAX_SEARCH_NODES`] partial representations.
+    pub fn find(
+        &self,
+        number: &BigInt,
+        decomposition: Decomposition,
+    ) -> Result<Option<Vec<isize>>> {
+        let mut best = None;
+        match decomposition {
+            Decomposition::Greedy | Decomposition::Smallest => {
+                self.search(number, &Cell::new(usize::MAX), &mut |digits| {
+                    best = Some(digits.to_vec());
+                    ControlFlow::Break(())
+                })?;
+            }
+            Decomposition::FewestNonzero => {
+                let limit = Cell::new(usize::MAX);
+                self.search(number, &limit, &mut |digits| {
+                    let nonzero = digits.iter().filter(|&&d| d != 0).count();
+                    // Only strictly better ones are looked for from now on,
+                    // so the first of the fewest is kept.
+                    limit.set(nonzero.saturating_sub(1));
+                    best = Some(digits.to_vec());
+                    match nonzero {
+                        0 => ControlFlow::Break(()),
+                        _ => ControlFlow::Continue(()),
+                    }
+                })?;
+            }
+        }
+        Ok(best)
+    }
+
+    /// Up to `limit` representations of `number`, least significant digit
+    /// first and without leading zeros, smallest first. Fails like
+    /// [`Decomposer::find`].
+    pub fn all(&self, number: &BigInt, limit: usize) -> Result<Vec<Vec<isize>>> {
+        let mut found = Vec::new();
+        if limit > 0 {
+            self.search(number, &Cell::new(usize::MAX), &mut |digits| {
+                found.push(digits.to_vec());
+                match found.len() < limit {
+                    true => ControlFlow::Continue(()),
+                    false => ControlFlow::Break(()),
+                }
+            })?;
+        }
+        Ok(found)
+    }
+
+    /// Visits the representations of `number` with at most `max_nonzero`
+    /// non-zero digits, smallest first.
+    fn search(
+        &self,
+        number: &BigInt,
+        max_nonzero: &Cell<usize>,
+        visit: &mut dyn FnMut(&[isize]) -> ControlFlow<()>,
+    ) -> Result<()> {
+        let mut search = Search {
+            max_nonzero,
+            visit,
+            nodes: 0,
+            digits: Vec::with_capacity(self.places.len()),
+        };
+        match self.descend(&mut search, self.places.len(), number, 0) {
+            ControlFlow::Break(Stop::Exhausted) => {
+                Err(ArithmeticError::SearchTooLarge(number.clone()).into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Picks the digit of position `count - 1`, with the digits of the search
+    /// holding those of the positions above it.
+    fn descend(
+        &self,
+        search: &mut Search,
+        count: usize,
+        remainder: &BigInt,
+        nonzero: usize,
+    ) -> ControlFlow<Stop> {
+        search.nodes += 1;
+        if search.nodes > MAX_SEARCH_NODES {
+            return ControlFlow::Break(Stop::Exhausted);
+        }
+        if nonzero > search.max_nonzero.get() {
+            return ControlFlow::Continue(());
+        }
+        let Some(position) = count.checked_sub(1) else {
+            if !remainder.is_zero() {
+                return ControlFlow::Continue(());
+            }
+            let mut representation: Vec<isize> = search.digits.iter().rev().copied().collect();
+            while representation.last() == Some(&0) {
+                representation.pop();
+            }
+            return match (search.visit)(&representation) {
+                ControlFlow::Break(()) => ControlFlow::Break(Stop::Done),
+                ControlFlow::Continue(()) => ControlFlow::Continue(()),
+            };
+        };
+        let place = &self.places[position];
+        let range = feasible_digits(
+            place,
+            &(remainder - &self.low[position]),
+            &(remainder - &self.high[position]),
+        );
+        let Some((min, max)) = range else {
+            return ControlFlow::Continue(());
+        };
+        for digit in min..=max {
+            search.digits.push(digit);
+            let flow = self.descend(
+                search,
+                position,
+                &(remainder - &place.weight * digit),
+                nonzero + usize::from(digit != 0),
+            );
+            search.digits.pop();
+            flow?;
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+/// The state of a search for representations.
+struct Search<'s> {
+    max_nonzero: &'s Cell<usize>,
+    visit: &'s mut dyn FnMut(&[isize]) -> ControlFlow<()>,
+    /// How many partial representations were visited.
+    nodes: usize,
+    /// The digits picked so far, most significant first.
+    digits: Vec<isize>,
+}
+
+/// Why a search stopped early.
+enum Stop {
+    /// The visitor has seen enough representations.
+    Done,
+    /// The search visited [`MAX_SEARCH_NODES`] partial representations.
+    Exhausted,
+}
+
+/// The lowest and highest sums the positions below each position reach, with
+/// an entry past the last position for all of them.
+pub(crate) fn sum_ranges(places: &[Place]) -> (Vec<BigInt>, Vec<BigInt>) {
+    let mut low = vec![BigInt::zero()];
+    let mut high = vec![BigInt::zero()];
+    for place in places {
+        let (min, max) = sum_range(place);
+        low.push(low.last().unwrap() + min);
+        high.push(high.last().unwrap() + max);
+    }
+    (low, high)
+}
+
+/// The lowest and highest multiples of the weight of `place` its digits give.
+fn sum_range(place: &Place) -> (BigInt, BigInt) {
+    let first = &place.weight * place.min_digit;
+    let last = &place.weight * place.max_digit;
+    match first <= last {
+        true => (first, last),
+        false => (last, first),
+    }
+}
+
+/// The range of digits `d` of `place` with `lowest <= weight * d <= highest`,
+/// if there are any.
+pub(crate) fn feasible_digits(
+    place: &Place,
+    highest: &BigInt,
+    lowest: &BigInt,
+) -> Option<(isize, isize)> {
+    let weight = &place.weight;
+    let (min, max) = match weight.sign() {
+        Sign::NoSign if lowest.is_positive() || highest.is_negative() => return None,
+        Sign::NoSign => return Some((place.min_digit, place.max_digit)),
+        Sign::Plus => (Integer::div_ceil(lowest, weight), highest.div_floor(weight)),
+        Sign::Minus => (Integer::div_ceil(highest, weight), lowest.div_floor(weight)),
+    };
+    let clamp = |digit: BigInt| match digit.to_isize() {
+        Some(digit) => digit,
+        None if digit.is_negative() => isize::MIN,
+        None => isize::MAX,
+    };
+    let min = clamp(min).max(place.min_digit);
+    let max = clamp(max).min(place.max_digit);
+    (min <= max).then_some((min, max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn places(weights: &[i64], max_digit: isize) -> Vec<Place> {
+        weights
+            .iter()
+            .map(|&weight| Place {
+                weight: BigInt::from(weight),
+                min_digit: 0,
+                max_digit,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_weights_that_do_not_divide() {
+        let places = places(&[1, 3, 4], 1);
+        let decomposer = Decomposer::new(&places);
+        let six = BigInt::from(6);
+
+        // Greedily 4 leaves 2, which 3 and 1 cannot make.
+        assert_eq!(
+            decomposer
+                .find(&BigInt::from(7), Decomposition::Smallest)
+                .unwrap(),
+            Some(vec![0, 1, 1])
+        );
+        assert_eq!(
+            decomposer.find(&six, Decomposition::Smallest).unwrap(),
+            None
+        );
+        assert_eq!(
+            decomposer.all(&BigInt::from(4), 10).unwrap(),
+            vec![vec![1, 1], vec![0, 0, 1]]
+        );
+    }
+
+    #[test]
+    fn test_canonical_orders() {
+        let places = places(&[1, 2, 3, 5], 2);
+        let decomposer = Decomposer::new(&places);
+        let five = BigInt::from(5);
+
+        assert_eq!(decomposer.all(&five, 100).unwrap().len(), 4);
+        assert_eq!(
+            decomposer.find(&five, Decomposition::Smallest).unwrap(),
+            Some(vec![1, 2])
+        );
+        assert_eq!(
+            decomposer
+                .find(&five, Decomposition::FewestNonzero)
+                .unwrap(),
+            Some(vec![0, 0, 0, 1])
+        );
+        assert_eq!(
+            decomposer
+                .find(&BigInt::zero(), Decomposition::FewestNonzero)
+                .unwrap(),
+            Some(vec![])
+        );
+    }
+
+    #[test]
+    fn test_negative_weights() {
+        let places = places(&[1, -2, 4, -8, 16], 1);
+        let decomposer = Decomposer::new(&places);
+
+        assert_eq!(
+            decomposer.all(&BigInt::from(-3), 10).unwrap(),
+            vec![vec![1, 0, 1, 1]]
+        );
+    }
+
+    #[test]
+    fn test_places_within() {
+        let powers = |position: usize| Ok(Some(places(&[1 << position], 1).remove(0)));
+
+        let places = places_within(&BigInt::from(5), powers).unwrap();
+
+        assert_eq!(places.len(), 3);
+    }
+
+    #[test]
+    fn test_cancelling_positions_past_the_bound() {
+        // Only 2 is within 5, and the odd weight 49 four positions up is
+        // needed, with the even ones between cancelling it down to 5.
+        let weights = [(2, 3), (6, 1), (12, 1), (24, 1), (49, 1), (200, 1)];
+        let place = |position: usize| {
+            Ok(weights.get(position).map(|&(weight, digit)| Place {
+                weight: BigInt::from(weight),
+                min_digit: -digit,
+                max_digit: digit,
+            }))
+        };
+
+        let places = places_within(&BigInt::from(5), place).unwrap();
+        let digits = Decomposer::new(&places)
+            .find(&BigInt::from(5), Decomposition::Smallest)
+            .unwrap();
+
+        assert_eq!(places.len(), 5);
+        assert_eq!(digits, Some(vec![-1, -1, -1, -1, 1]));
+    }
+
+    #[test]
+    fn test_search_budget() {
+        // Sums of even weights are never odd, which only the whole search
+        // can tell.
+        let places = places(&[2; 40], 1);
+
+        let error = Decomposer::new(&places)
+            .find(&BigInt::from(41), Decomposition::Smallest)
+            .unwrap_err();
+
+        assert!(matches!(
+            error,
+            crate::Error::Arithmetic(ArithmeticError::SearchTooLarge(_))
+        ));
+    }
+}
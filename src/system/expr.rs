@@ -0,0 +1,231 @@
+mod parser;
+
+use super::{decompose_rational, integer, Decomposition, Digit, NumeralSystem, Place, Symbols};
+use crate::config::System;
+use crate::error::{ArithmeticError, ConfigError, ParseError, ParseErrorReason, Result};
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::Zero;
+use std::cell::RefCell;
+
+pub use parser::Expr;
+
+/// A positional system whose weights come from an [`Expr`] over the position,
+/// evaluated natively on big integers. Weights listed in the config are used
+/// for the first positions, so that expressions referring to earlier weights
+/// have a start. The last digit before the radix point is at the origin, and
+/// there are no digits after it.
+#[derive(Debug)]
+pub struct ExprSystem {
+    source: String,
+    expression: Expr,
+    symbols: Symbols,
+    decomposition: Decomposition,
+    origin: isize,
+    /// The weights of the positions from the origin up evaluated so far.
+    weights: RefCell<Vec<BigInt>>,
+}
+
+impl ExprSystem {
+    pub fn new(system: &System) -> Result<Self> {
+        let source = system
+            .get_expression()
+            .ok_or(ConfigError::MissingExpression)?;
+        let weights = system
+            .get_weights()
+            .iter()
+            .enumerate()
+            .map(|(position, weight)| match weight.is_integer() {
+                true => Ok(weight.to_integer()),
+                false => Err(ConfigError::FractionalWeight {
+                    position,
+                    weight: weight.clone(),
+                }),
+            })
+            .collect::<std::result::Result<_, _>>()?;
+        Ok(Self {
+            source: source.to_string(),
+            expression: Expr::parse(source).map_err(ConfigError::from)?,
+            symbols: Symbols::new(system)?,
+            decomposition: system.get_decomposition(),
+            origin: system.get_origin(),
+            weights: RefCell::new(weights),
+        })
+    }
+
+    /// The weight of `position`, evaluating the lower positions first so
+    /// that the expression can refer to them.
+    fn weight(&self, position: usize) -> Result<BigInt> {
+        let mut weights = self.weights.borrow_mut();
+        while weights.len() <= position {
+            let n = weights.len() as isize + self.origin;
+            let known = &*weights;
+            let weight = self
+                .expression
+                .evaluate(n, &|k| {
+                    let index = usize::try_from(k - self.origin).ok()?;
+                    known.get(index).cloned()
+                })
+                .map_err(|message| ArithmeticError::Expression {
+                    position: n,
+                    message,
+                })?;
+            weights.push(weight);
+        }
+        Ok(weights[position].clone())
+    }
+
+    fn place(&self, position: usize) -> Result<Place> {
+        let (min_digit, max_digit) = self
+            .symbols
+            .digit_range(position as isize)
+            .unwrap_or_default();
+        Ok(Place {
+            weight: self.weight(position)?,
+            min_digit,
+            max_digit,
+        })
+    }
+}
+
+impl NumeralSystem for ExprSystem {
+    fn parse(&self, input: &str) -> Result<BigInt> {
+        integer(self.parse_rational(input)?)
+    }
+
+    fn render(&self, number: &BigInt) -> Result<String> {
+        self.render_rational(&BigRational::from(number.clone()))
+    }
+
+    /// Rejects digits after the radix point.
+    fn parse_rational(&self, input: &str) -> Result<BigRational> {
+        let numeral = self.symbols.read_numeral(input)?;
+        if !numeral.fraction.is_empty() {
+            return Err(ParseError {
+                input: input.to_string(),
+                position: numeral.offset(-1),
+                reason: ParseErrorReason::TooManyFractionDigits(0),
+            }
+            .into());
+        }
+        let mut number = BigInt::zero();
+        for (position, &value) in numeral.integer.iter().enumerate() {
+            number += self.weight(position)? * value;
+        }
+        let number = BigRational::from(number);
+        Ok(if numeral.negative { -number } else { number })
+    }
+
+    /// Weights may be negative or alternate in sign. A leading `-` is only
+    /// written for numbers the weights cannot reach.
+    fn render_rational(&self, number: &BigRational) -> Result<String> {
+        self.symbols.write_number(number, |number| {
+            decompose_rational(
+                number,
+                self.decomposition,
+                |position| self.place(position).map(Some),
+                |_| Ok(None),
+            )
+        })
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "weights {} with digits {}",
+            self.source,
+            self.symbols.describe()
+        )
+    }
+
+    fn place_at(&self, position: usize) -> Result<Option<Place>> {
+        self.place(position).map(Some)
+    }
+
+    fn digits(&self, input: &str) -> Result<Option<Vec<Digit>>> {
+        self.symbols.weighed_digits(input, self).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+
+    fn system(expression: &str, symbols: &str) -> ExprSystem {
+        ExprSystem::new(&System::expr(expression, symbols)).unwrap()
+    }
+
+    #[test]
+    fn test_hexadecimal() {
+        let hex = system("16^n", "0123456789ABCDEF");
+
+        assert_eq!(hex.parse("FF").unwrap(), BigInt::from(255));
+        assert_eq!(hex.render(&BigInt::from(4096)).unwrap(), "1000");
+    }
+
+    #[test]
+    fn test_factoradic() {
+        let factoradic = system("(n+1)!", "0123456789");
+
+        assert_eq!(factoradic.parse("321").unwrap(), BigInt::from(23));
+        assert_eq!(factoradic.render(&BigInt::from(719)).unwrap(), "54321");
+    }
+
+    #[test]
+    fn test_earlier_weights() {
+        // Fibonacci weights from 1 and 2 write numbers in Zeckendorf form.
+        let zeckendorf =
+            System::expr("w(n-1) + w(n-2)", "01").with_weights([1, 2].map(BigInt::from));
+        let fibonacci = ExprSystem::new(&zeckendorf).unwrap();
+
+        assert_eq!(fibonacci.parse("10001").unwrap(), BigInt::from(9));
+        assert_eq!(fibonacci.render(&BigInt::from(100)).unwrap(), "1000010100");
+        assert_eq!(
+            system("w(n)", "01").parse("1").unwrap_err().to_string(),
+            "weight expression failed at position 0: w(0) is not the weight of an earlier position"
+        );
+    }
+
+    #[test]
+    fn test_origin() {
+        let factoradic = ExprSystem::new(&System::expr("n!", "0123456789").with_origin(1)).unwrap();
+
+        assert_eq!(factoradic.parse("321").unwrap(), BigInt::from(23));
+        let Err(Error::Parse(error)) = factoradic.parse_rational("1.5") else {
+            panic!("expected digits after the radix point to be rejected");
+        };
+        assert_eq!(error.position, 2);
+        assert_eq!(error.reason, ParseErrorReason::TooManyFractionDigits(0));
+    }
+
+    #[test]
+    fn test_errors() {
+        let config = ExprSystem::new(&System::expr("2 ^", "01")).unwrap_err();
+        let arithmetic = system("2^(n-1)", "01").parse("1").unwrap_err();
+
+        assert_eq!(
+            config.to_string(),
+            "invalid expression \"2 ^\": expected a number, `n`, `w(...)` or `(` at column 3"
+        );
+        assert!(matches!(
+            arithmetic,
+            Error::Arithmetic(ArithmeticError::Expression { position: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn test_squaring_is_bounded() {
+        let squares = System::expr("w(n-1)*w(n-1)", "0123456789").with_weights([BigInt::from(2)]);
+        let input = format!("1{}", "0".repeat(39));
+
+        let error = ExprSystem::new(&squares)
+            .unwrap()
+            .parse(&input)
+            .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "weight expression failed at position 24: result is too large"
+        );
+    }
+}
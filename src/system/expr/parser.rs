@@ -0,0 +1,306 @@
+use crate::error::ExpressionError;
+use num_bigint::BigInt;
+use num_traits::{One, Signed, ToPrimitive};
+use std::{iter::Peekable, str::CharIndices};
+
+/// Largest result, in bits, that an operation may produce.
+const MAX_BITS: u64 = 1 << 24;
+
+/// Largest number `!` is taken of.
+const MAX_FACTORIAL: u32 = 20_000;
+
+/// A weight expression over the position `n`. Sums, differences and products
+/// bind as usual, `^` binds tighter than a leading `-` and to the right, and
+/// `!` tighter still, so `-(10^6)^n` is the negated power and `2^n!` is
+/// `2^(n!)`. `w(k)` is the weight of an earlier position `k`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Integer(BigInt),
+    Position,
+    Weight(Box<Expr>),
+    Neg(Box<Expr>),
+    Add(Box<Expr>, Box<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+    Pow(Box<Expr>, Box<Expr>),
+    Factorial(Box<Expr>),
+}
+
+impl Expr {
+    /// Reads an expression, reporting the first character that does not fit.
+    pub fn parse(source: &str) -> Result<Self, ExpressionError> {
+        let mut parser = Parser {
+            source,
+            chars: source.char_indices().peekable(),
+        };
+        let expr = parser.sum()?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some((column, letter)) => Err(parser.error(column, format!("unexpected {:?}", letter))),
+        }
+    }
+
+    /// The value at position `n`, where `weight(k)` is the weight of position
+    /// `k`, if known. Only positions below `n` may be referred to.
+    pub fn evaluate(
+        &self,
+        n: isize,
+        weight: &dyn Fn(isize) -> Option<BigInt>,
+    ) -> Result<BigInt, String> {
+        let evaluate = |expr: &Expr| expr.evaluate(n, weight);
+        Ok(match self {
+            Expr::Integer(value) => value.clone(),
+            Expr::Position => BigInt::from(n),
+            Expr::Weight(position) => {
+                let position = evaluate(position)?;
+                match position.to_isize().filter(|&k| k < n).and_then(weight) {
+                    Some(weight) => weight,
+                    None => {
+                        return Err(format!(
+                            "w({}) is not the weight of an earlier position",
+                            position
+                        ))
+                    }
+                }
+            }
+            Expr::Neg(a) => -evaluate(a)?,
+            Expr::Add(a, b) => {
+                let (a, b) = (evaluate(a)?, evaluate(b)?);
+                bounded(a.bits().max(b.bits()) + 1, || a + b)?
+            }
+            Expr::Sub(a, b) => {
+                let (a, b) = (evaluate(a)?, evaluate(b)?);
+                bounded(a.bits().max(b.bits()) + 1, || a - b)?
+            }
+            Expr::Mul(a, b) => {
+                let (a, b) = (evaluate(a)?, evaluate(b)?);
+                bounded(a.bits() + b.bits(), || a * b)?
+            }
+            Expr::Pow(a, b) => pow(&evaluate(a)?, &evaluate(b)?)?,
+            Expr::Factorial(a) => factorial(&evaluate(a)?)?,
+        })
+    }
+}
+
+/// The result of `operation`, which has at most `bits` bits, unless that is
+/// more than [`MAX_BITS`].
+fn bounded(bits: u64, operation: impl FnOnce() -> BigInt) -> Result<BigInt, String> {
+    match bits <= MAX_BITS {
+        true => Ok(operation()),
+        false => Err("result is too large".to_string()),
+    }
+}
+
+fn pow(base: &BigInt, exponent: &BigInt) -> Result<BigInt, String> {
+    if exponent.is_negative() {
+        return Err(format!("negative exponent {}", exponent));
+    }
+    let exponent = exponent.to_u32().filter(|&e| {
+        base.bits().saturating_mul(u64::from(e)) <= MAX_BITS || base.magnitude() <= &1u32.into()
+    });
+    match exponent {
+        Some(exponent) => Ok(base.pow(exponent)),
+        None => Err("power is too large".to_string()),
+    }
+}
+
+fn factorial(n: &BigInt) -> Result<BigInt, String> {
+    if n.is_negative() {
+        return Err(format!("factorial of negative number {}", n));
+    }
+    match n.to_u32().filter(|&n| n <= MAX_FACTORIAL) {
+        Some(n) => Ok((2..=n).fold(BigInt::one(), |product, k| product * k)),
+        None => Err(format!("factorial of {} is too large", n)),
+    }
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl Parser<'_> {
+    /// The next character that is not whitespace, with its byte offset.
+    fn peek(&mut self) -> Option<(usize, char)> {
+        while let Some(&(_, letter)) = self.chars.peek() {
+            if !letter.is_whitespace() {
+                break;
+            }
+            self.chars.next();
+        }
+        self.chars.peek().copied()
+    }
+
+    /// Consumes `letter` if it comes next.
+    fn eat(&mut self, letter: char) -> bool {
+        let next = self.peek().is_some_and(|(_, next)| next == letter);
+        if next {
+            self.chars.next();
+        }
+        next
+    }
+
+    fn expect(&mut self, letter: char) -> Result<(), ExpressionError> {
+        if self.eat(letter) {
+            return Ok(());
+        }
+        let (offset, message) = match self.peek() {
+            Some((offset, next)) => (offset, format!("expected {:?}, found {:?}", letter, next)),
+            None => (self.source.len(), format!("expected {:?}", letter)),
+        };
+        Err(self.error(offset, message))
+    }
+
+    fn error(&self, offset: usize, message: String) -> ExpressionError {
+        ExpressionError {
+            expression: self.source.to_string(),
+            column: self.source[..offset].chars().count(),
+            message,
+        }
+    }
+
+    fn sum(&mut self) -> Result<Expr, ExpressionError> {
+        let mut expr = self.product()?;
+        loop {
+            if self.eat('+') {
+                expr = Expr::Add(Box::new(expr), Box::new(self.product()?));
+            } else if self.eat('-') {
+                expr = Expr::Sub(Box::new(expr), Box::new(self.product()?));
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn product(&mut self) -> Result<Expr, ExpressionError> {
+        let mut expr = self.unary()?;
+        while self.eat('*') {
+            expr = Expr::Mul(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, ExpressionError> {
+        if self.eat('-') {
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        let base = self.postfix()?;
+        if self.eat('^') {
+            return Ok(Expr::Pow(Box::new(base), Box::new(self.unary()?)));
+        }
+        Ok(base)
+    }
+
+    fn postfix(&mut self) -> Result<Expr, ExpressionError> {
+        let mut expr = self.primary()?;
+        while self.eat('!') {
+            expr = Expr::Factorial(Box::new(expr));
+        }
+        Ok(expr)
+    }
+
+    fn primary(&mut self) -> Result<Expr, ExpressionError> {
+        let Some((offset, letter)) = self.peek() else {
+            let message = "expected a number, `n`, `w(...)` or `(`".to_string();
+            return Err(self.error(self.source.len(), message));
+        };
+        match letter {
+            '0'..='9' => {
+                let mut end = offset;
+                while let Some(&(index, digit)) = self.chars.peek() {
+                    if !digit.is_ascii_digit() {
+                        break;
+                    }
+                    end = index + 1;
+                    self.chars.next();
+                }
+                let value = self.source[offset..end].parse().unwrap_or_default();
+                Ok(Expr::Integer(value))
+            }
+            'n' => {
+                self.chars.next();
+                Ok(Expr::Position)
+            }
+            'w' => {
+                self.chars.next();
+                self.expect('(')?;
+                let position = self.sum()?;
+                self.expect(')')?;
+                Ok(Expr::Weight(Box::new(position)))
+            }
+            '(' => {
+                self.chars.next();
+                let expr = self.sum()?;
+                self.expect(')')?;
+                Ok(expr)
+            }
+            _ => Err(self.error(
+                offset,
+                format!(
+                    "expected a number, `n`, `w(...)` or `(`, found {:?}",
+                    letter
+                ),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(source: &str, n: isize) -> Result<BigInt, String> {
+        let weights = |k: isize| (k >= 0).then(|| BigInt::from(10 * k));
+        Expr::parse(source).unwrap().evaluate(n, &weights)
+    }
+
+    #[test]
+    fn test_precedence() {
+        assert_eq!(eval("2^n + 1", 3), Ok(BigInt::from(9)));
+        assert_eq!(
+            eval("-(10^6)^n", 2),
+            Ok(BigInt::from(-1_000_000_000_000i64))
+        );
+        assert_eq!(eval("-2^2", 0), Ok(BigInt::from(-4)));
+        assert_eq!(eval("2^3^2", 0), Ok(BigInt::from(512)));
+        assert_eq!(eval("2^n!", 3), Ok(BigInt::from(64)));
+        assert_eq!(eval("(n+1)! - 2*3", 3), Ok(BigInt::from(18)));
+        assert_eq!(eval(" 1 - 2 - 3 ", 0), Ok(BigInt::from(-4)));
+    }
+
+    #[test]
+    fn test_earlier_weights() {
+        assert_eq!(eval("w(n-1) * 3", 2), Ok(BigInt::from(30)));
+        assert!(eval("w(n)", 2).is_err());
+        assert!(eval("w(n-1)", 0).is_err());
+    }
+
+    #[test]
+    fn test_evaluation_errors() {
+        for source in ["2^(0-1)", "(0-1)!", "100000!", "10^(10^9)"] {
+            assert!(eval(source, 0).is_err(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        let error = |source: &str| {
+            let error = Expr::parse(source).unwrap_err();
+            (error.column, error.message)
+        };
+
+        assert_eq!(
+            error("2 ^ x"),
+            (
+                4,
+                "expected a number, `n`, `w(...)` or `(`, found 'x'".to_string()
+            )
+        );
+        assert_eq!(error("w(n"), (3, "expected ')'".to_string()));
+        assert_eq!(error("n n"), (2, "unexpected 'n'".to_string()));
+        assert_eq!(
+            error(""),
+            (0, "expected a number, `n`, `w(...)` or `(`".to_string())
+        );
+    }
+}
@@ -0,0 +1,840 @@
+mod alphabet;
+mod bigint;
+mod modules;
+mod sandbox;
+
+use super::{
+    decompose_rational, integer, Digit, FractionPlace, Numeral, NumeralSystem, Place, Symbols,
+};
+use crate::config::System;
+use crate::error::{LuaError, ParseError, ParseErrorReason, Result};
+use bigint::LuaBigInt;
+use sandbox::Guard;
+
+use mlua::prelude::*;
+use mlua::RegistryKey;
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::Zero;
+use std::{cell::RefCell, collections::HashMap, fs, path::Path, rc::Rc};
+
+pub use alphabet::LuaAlphabet;
+pub use sandbox::{Limit, Sandbox};
+
+/// Wraps a function so that it computes the result for each argument once.
+/// Arguments are compared by their string form, so `f(3)` and
+/// `f(bigint.new(3))` share a result.
+const LUA_MEMOIZE: &str = r#"
+return function (f)
+    local cache = {}
+    return function (n, ...)
+        local key = tostring(n)
+        local result = cache[key]
+        if result == nil then
+            result = f(n, ...)
+            cache[key] = result
+        end
+        return result
+    end
+end
+"#;
+
+/// A system whose weight at position `n` is computed by a lua function.
+/// The function is compiled once into a sandboxed lua state owned by the
+/// system, and each weight is computed at most once per system. Weights may
+/// be returned as `bigint` values, integers or decimal strings.
+///
+/// The function may also return `{weight, min_digit, max_digit}` to limit the
+/// digits of a position, as mixed radix systems need.
+///
+/// The last digit before the radix point is at the system's origin, and the
+/// digits after it at the positions below. Weights there may be fractions,
+/// returned as strings such as `"1/6"`.
+///
+/// The function is called as `f(n, context)`, where `context` holds
+/// `position`, the `weights` of the positions below as `bigint` values, the
+/// position's `alphabet` as `{symbol, value}` entries, `min_digit` and
+/// `max_digit`, and the system's `params`, which are also the global `params`.
+#[derive(Debug)]
+pub struct LuaSystem {
+    system: System,
+    symbols: Symbols,
+    origin: isize,
+    lua: Lua,
+    guard: Rc<Guard>,
+    function: RegistryKey,
+    /// The parts of the context shared by every call.
+    context: RegistryKey,
+    places: RefCell<HashMap<isize, Place>>,
+    fractions: RefCell<HashMap<isize, FractionPlace>>,
+}
+
+impl LuaSystem {
+    pub fn new(system: &System) -> Result<Self> {
+        let (source, chunk_name) = match (system.get_function(), system.get_function_file()) {
+            (Some(function), _) => (function.to_string(), "=function".to_string()),
+            (None, Some(path)) => read_chunk(path)?,
+            (None, None) => return Err(LuaError::MissingFunction.into()),
+        };
+        let (lua, guard) = system.get_sandbox().lua()?;
+        bigint::install(&lua).map_err(LuaError::Library)?;
+        modules::install(&lua, system.get_modules(), system.get_libraries())?;
+        let params = lua
+            .to_value(system.get_params())
+            .map_err(LuaError::Library)?;
+        lua.globals()
+            .set("params", params.clone())
+            .map_err(LuaError::Library)?;
+        let function = guard.run(None, || {
+            compile(&lua, &source, chunk_name).map_err(LuaError::Compile)
+        })?;
+        let context = shared_context(&lua, params).map_err(LuaError::Library)?;
+        Ok(Self {
+            system: system.clone(),
+            symbols: Symbols::new(system)?,
+            origin: system.get_origin(),
+            lua,
+            guard,
+            function,
+            context,
+            places: RefCell::new(HashMap::new()),
+            fractions: RefCell::new(HashMap::new()),
+        })
+    }
+
+    /// The weight and digit range of `position`, at or above the origin,
+    /// computing the missing places of lower positions from the origin up
+    /// first so the context can list them.
+    fn place(&self, position: isize) -> Result<Place> {
+        if let Some(place) = self.places.borrow().get(&position) {
+            return Ok(place.clone());
+        }
+        let mut first = position;
+        while first > self.origin && !self.places.borrow().contains_key(&(first - 1)) {
+            first -= 1;
+        }
+        for lower in first..position {
+            self.evaluate(lower)?;
+        }
+        self.evaluate(position)
+    }
+
+    fn evaluate(&self, position: isize) -> Result<Place> {
+        let shared: LuaTable = self
+            .lua
+            .registry_value(&self.context)
+            .map_err(LuaError::Library)?;
+        let (weight, min_digit, max_digit) = self.call(&shared, position)?;
+        let place = Place {
+            weight: weight.to_integer(),
+            min_digit,
+            max_digit,
+        };
+        let weights: LuaTable = shared.get("weights").map_err(LuaError::Library)?;
+        // The weight counts against the memory limit like those lua creates.
+        self.guard.run(Some(position), || {
+            LuaBigInt::new(&self.lua, place.weight.clone())
+                .and_then(|weight| weights.raw_set(position, weight))
+                .map_err(LuaError::Library)
+        })?;
+        self.places.borrow_mut().insert(position, place.clone());
+        Ok(place)
+    }
+
+    /// The weight and digit range of the `index`th position after the radix
+    /// point, which is `index` below the origin.
+    fn fraction_place(&self, index: usize) -> Result<FractionPlace> {
+        let position = self.origin - index as isize;
+        if let Some(place) = self.fractions.borrow().get(&position) {
+            return Ok(place.clone());
+        }
+        let shared: LuaTable = self
+            .lua
+            .registry_value(&self.context)
+            .map_err(LuaError::Library)?;
+        let (weight, min_digit, max_digit) = self.call(&shared, position)?;
+        let place = FractionPlace {
+            weight,
+            min_digit,
+            max_digit,
+        };
+        self.fractions.borrow_mut().insert(position, place.clone());
+        Ok(place)
+    }
+
+    /// Calls the function for `position` and reads the place it returns.
+    fn call(&self, shared: &LuaTable, position: isize) -> Result<(BigRational, isize, isize)> {
+        let result: LuaValue = self.guard.run(Some(position), || {
+            let calculate_base: mlua::Function = self
+                .lua
+                .registry_value(&self.function)
+                .map_err(LuaError::Library)?;
+            let context = self.context(shared, position).map_err(LuaError::Library)?;
+            calculate_base
+                .call((position, context))
+                .map_err(|source| LuaError::Call { position, source })
+        })?;
+        self.read_place(position, result)
+    }
+
+    /// Reads what the function returned for `position`: either the weight, or
+    /// a table `{weight, min_digit, max_digit}` whose fields may also be given
+    /// by name. Missing digit bounds are those of the alphabet. Only weights
+    /// after the radix point may be fractions.
+    fn read_place(&self, position: isize, result: LuaValue) -> Result<(BigRational, isize, isize)> {
+        let fraction = position < self.origin;
+        let invalid = |value: &LuaValue| LuaError::InvalidWeight {
+            position,
+            value: describe_value(value),
+            expected: match fraction {
+                true => "an integer or a fraction such as \"1/6\"",
+                false => "an integer",
+            },
+        };
+        let (weight, min_digit, max_digit) = match &result {
+            LuaValue::Table(table) => (
+                place_field(table, "weight", 1),
+                place_field(table, "min_digit", 2),
+                place_field(table, "max_digit", 3),
+            ),
+            _ => (result.clone(), LuaValue::Nil, LuaValue::Nil),
+        };
+        let value = match fraction {
+            true => bigint::to_rational(&weight),
+            false => bigint::to_bigint(&weight).map(BigRational::from),
+        };
+        let weight = value.ok_or_else(|| invalid(&weight))?;
+        let (min, max) = self
+            .symbols
+            .digit_range(position - self.origin)
+            .unwrap_or_default();
+        let bound = |value: &LuaValue, default: isize| match value {
+            LuaValue::Nil => Ok(default),
+            value => bigint::to_bigint(value)
+                .and_then(|bound| isize::try_from(bound).ok())
+                .ok_or_else(|| invalid(value)),
+        };
+        let min_digit = bound(&min_digit, min)?;
+        let max_digit = bound(&max_digit, max)?;
+        if min_digit > max_digit {
+            return Err(LuaError::EmptyDigitRange {
+                position,
+                min_digit,
+                max_digit,
+            }
+            .into());
+        }
+        Ok((weight, min_digit, max_digit))
+    }
+
+    fn context<'lua>(
+        &'lua self,
+        shared: &LuaTable<'lua>,
+        position: isize,
+    ) -> LuaResult<LuaTable<'lua>> {
+        let context = self.lua.create_table()?;
+        for pair in shared.clone().pairs::<LuaValue, LuaValue>() {
+            let (key, value) = pair?;
+            context.raw_set(key, value)?;
+        }
+        context.raw_set("position", position)?;
+        let mut alphabet = self.system.get_alphabet_at(position - self.origin).clone();
+        alphabet.sort_unstable();
+        let letters = self.lua.create_table()?;
+        for &(value, symbol) in &alphabet {
+            let letter = self.lua.create_table()?;
+            letter.raw_set("symbol", symbol.to_string())?;
+            letter.raw_set("value", value)?;
+            letters.raw_push(letter)?;
+        }
+        context.raw_set("alphabet", letters)?;
+        if let Some((min, max)) = self.symbols.digit_range(position - self.origin) {
+            context.raw_set("min_digit", min)?;
+            context.raw_set("max_digit", max)?;
+        }
+        Ok(context)
+    }
+
+    fn numeral(&self, number: &BigRational) -> Result<Numeral> {
+        decompose_rational(
+            number,
+            self.system.get_decomposition(),
+            |position| self.place(position as isize + self.origin).map(Some),
+            |index| self.fraction_place(index).map(Some),
+        )
+    }
+}
+
+/// A field of a place table, by `name` or else by `index`.
+fn place_field<'lua>(table: &LuaTable<'lua>, name: &str, index: i64) -> LuaValue<'lua> {
+    match table.raw_get(name) {
+        Ok(LuaValue::Nil) | Err(_) => table.raw_get(index).unwrap_or(LuaValue::Nil),
+        Ok(value) => value,
+    }
+}
+
+/// Reads a lua file along with the chunk name that makes lua report errors
+/// as `path:line:`.
+fn read_chunk(path: &Path) -> std::result::Result<(String, String), LuaError> {
+    let source = fs::read_to_string(path).map_err(|source| LuaError::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    Ok((source, format!("@{}", path.display())))
+}
+
+/// Evaluates the chunk defining a weight function and memoizes the function.
+/// Globals bound to the function are rebound to the memoized version, so
+/// recursive definitions such as `f = function (n) ... f(n - 1) ... end`
+/// reuse earlier results instead of recomputing them.
+fn compile(lua: &Lua, source: &str, chunk_name: String) -> LuaResult<RegistryKey> {
+    let function: LuaFunction = lua.load(source).set_name(chunk_name).eval()?;
+    let memoize: LuaFunction = lua.load(LUA_MEMOIZE).set_name("=memoize").eval()?;
+    let memoized: LuaFunction = memoize.call(function.clone())?;
+    let globals = lua.globals();
+    let names = globals
+        .clone()
+        .pairs::<LuaValue, LuaValue>()
+        .filter_map(|pair| match pair {
+            Ok((name, LuaValue::Function(global))) if global == function => Some(Ok(name)),
+            Ok(_) => None,
+            Err(e) => Some(Err(e)),
+        })
+        .collect::<LuaResult<Vec<_>>>()?;
+    for name in names {
+        globals.raw_set(name, memoized.clone())?;
+    }
+    lua.create_registry_value(memoized)
+}
+
+/// The context fields that are the same for every position.
+fn shared_context<'lua>(lua: &'lua Lua, params: LuaValue<'lua>) -> LuaResult<RegistryKey> {
+    let context = lua.create_table()?;
+    context.raw_set("weights", lua.create_table()?)?;
+    context.raw_set("params", params)?;
+    lua.create_registry_value(context)
+}
+
+/// A short rendering of a lua value for error messages.
+fn describe_value(value: &LuaValue) -> String {
+    match value {
+        LuaValue::Nil => "nil".to_string(),
+        LuaValue::Boolean(b) => b.to_string(),
+        LuaValue::Integer(n) => n.to_string(),
+        LuaValue::Number(n) => n.to_string(),
+        LuaValue::String(s) => s.to_string_lossy().into_owned(),
+        other => other.type_name().to_string(),
+    }
+}
+
+impl NumeralSystem for LuaSystem {
+    fn parse(&self, input: &str) -> Result<BigInt> {
+        integer(self.parse_rational(input)?)
+    }
+
+    fn render(&self, number: &BigInt) -> Result<String> {
+        self.render_rational(&BigRational::from(number.clone()))
+    }
+
+    /// Rejects digits outside the range the function gives for their position.
+    fn parse_rational(&self, input: &str) -> Result<BigRational> {
+        let numeral = self.symbols.read_numeral(input)?;
+        let check = |position: isize, value: isize, min: isize, max: isize| {
+            if (min..=max).contains(&value) {
+                return Ok(());
+            }
+            Err(ParseError {
+                input: input.to_string(),
+                position: numeral.offset(position),
+                reason: ParseErrorReason::DigitOutOfRange { value, min, max },
+            })
+        };
+        let mut integer = BigInt::zero();
+        for (position, &value) in numeral.integer.iter().enumerate() {
+            let place = self.place(position as isize + self.origin)?;
+            check(position as isize, value, place.min_digit, place.max_digit)?;
+            integer += place.weight * value;
+        }
+        let mut number = BigRational::from(integer);
+        for (index, &value) in numeral.fraction.iter().enumerate() {
+            let place = self.fraction_place(index + 1)?;
+            check(-1 - index as isize, value, place.min_digit, place.max_digit)?;
+            number += place.weight * BigInt::from(value);
+        }
+        Ok(if numeral.negative { -number } else { number })
+    }
+
+    /// Weights may be negative or alternate in sign. A leading `-` is only
+    /// written for numbers the weights cannot reach.
+    fn render_rational(&self, number: &BigRational) -> Result<String> {
+        self.symbols
+            .write_number(number, |number| self.numeral(number))
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "weights from a lua function with digits {}",
+            self.symbols.describe()
+        )
+    }
+
+    fn place_at(&self, position: usize) -> Result<Option<Place>> {
+        self.place(position as isize + self.origin).map(Some)
+    }
+
+    fn fraction_place_at(&self, position: usize) -> Result<Option<FractionPlace>> {
+        self.fraction_place(position).map(Some)
+    }
+
+    fn digits(&self, input: &str) -> Result<Option<Vec<Digit>>> {
+        self.symbols.weighed_digits(input, self).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{ArithmeticError, Error};
+    use std::str::FromStr;
+
+    impl LuaSystem {
+        fn calculate_base(&self, position: isize) -> Result<BigInt> {
+            Ok(self.place(position)?.weight)
+        }
+    }
+
+    #[test]
+    fn test_calculate_base_1() {
+        let lua_function = r#"f = function (n) n = BigNum.new(n) base = BigNum.new(10^6) return tostring(base^n) end return f"#;
+
+        let lua_system = LuaSystem::new(&System::lua(lua_function, "0123456789")).unwrap();
+
+        assert_eq!(lua_system.calculate_base(1).unwrap(), BigInt::from(1000000));
+    }
+
+    #[test]
+    fn test_calculate_base_2() {
+        let lua_function = r#"f = function (n) n = BigNum.new(n) base = BigNum.new(10^6) return tostring(base^n) end return f"#;
+
+        let lua_system = LuaSystem::new(&System::lua(lua_function, "0123456789")).unwrap();
+
+        assert_eq!(
+            lua_system.calculate_base(2).unwrap(),
+            BigInt::from_str("1000000000000").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_calculate_base_0() {
+        let lua_function = r#"f = function (n) n = BigNum.new(n) base = BigNum.new(10^6) return tostring(base^n) end return f"#;
+
+        let lua_system = LuaSystem::new(&System::lua(lua_function, "0123456789")).unwrap();
+
+        assert_eq!(lua_system.calculate_base(0).unwrap(), BigInt::from(1));
+    }
+
+    #[test]
+    fn test_factorial_1() {
+        let lua_function = r#"f = function (n) n = BigNum.new(n) one = BigNum.new(1) if n <= one then return tostring(one) else return tostring(n * f(n - one)) end end return f"#;
+
+        let lua_system = LuaSystem::new(&System::lua(lua_function, "0123456789")).unwrap();
+
+        assert_eq!(lua_system.calculate_base(1).unwrap(), BigInt::from(1));
+    }
+
+    #[test]
+    fn test_factorial_2() {
+        let lua_function = r#"f = function (n) n = BigNum.new(n) one = BigNum.new(1) if n <= one then return tostring(one) else return tostring(n * f(n - one)) end end return f"#;
+
+        let lua_system = LuaSystem::new(&System::lua(lua_function, "0123456789")).unwrap();
+
+        assert_eq!(lua_system.calculate_base(2).unwrap(), BigInt::from(2));
+    }
+
+    #[test]
+    fn test_factorial_3() {
+        let lua_function = r#"f = function (n) n = BigNum.new(n) one = BigNum.new(1) if n <= one then return tostring(one) else return tostring(n * f(n - one)) end end return f"#;
+
+        let lua_system = LuaSystem::new(&System::lua(lua_function, "0123456789")).unwrap();
+
+        assert_eq!(lua_system.calculate_base(3).unwrap(), BigInt::from(6));
+    }
+
+    fn limited(function: &str, sandbox: Sandbox) -> LuaSystem {
+        LuaSystem::new(&System::lua(function, "01").with_sandbox(sandbox)).unwrap()
+    }
+
+    fn limit_hit(lua_system: &LuaSystem) -> Option<Limit> {
+        match lua_system.calculate_base(1) {
+            Err(Error::Lua(LuaError::Limit { limit, .. })) => Some(limit),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_unsafe_libraries_are_missing() {
+        for library in ["os", "io", "debug", "dofile", "load", "package.searchers"] {
+            let function = format!(
+                "return function (n) return tostring({} == nil) end",
+                library
+            );
+            let lua_system = limited(&function, Sandbox::default());
+
+            assert_eq!(
+                lua_system.calculate_base(0).unwrap_err().to_string(),
+                format!("lua function returned \"true\" at position 0, expected an integer"),
+                "{} is available",
+                library
+            );
+        }
+    }
+
+    #[test]
+    fn test_require() {
+        let system = System::lua(
+            "require 'BigNum' local h = require('helpers') return function (n) return h.square(BigNum.new(n)) end",
+            "01",
+        )
+        .with_module("helpers", "return { square = function (n) return n * n end }");
+        let lua_system = LuaSystem::new(&system).unwrap();
+
+        assert_eq!(lua_system.calculate_base(12).unwrap(), BigInt::from(144));
+
+        let missing = LuaSystem::new(&System::lua("require 'os' return print", "01"));
+        assert!(missing
+            .unwrap_err()
+            .to_string()
+            .contains("module 'os' not found"));
+    }
+
+    #[test]
+    fn test_instruction_limit() {
+        let sandbox = Sandbox {
+            instruction_limit: 10_000,
+            ..Sandbox::default()
+        };
+        let lua_system = limited("return function (n) while true do end end", sandbox);
+
+        assert_eq!(limit_hit(&lua_system), Some(Limit::Instructions(10_000)));
+    }
+
+    #[test]
+    fn test_time_limit() {
+        let sandbox = Sandbox {
+            instruction_limit: 0,
+            timeout_ms: 50,
+            ..Sandbox::default()
+        };
+        let lua_system = limited("return function (n) while true do end end", sandbox);
+
+        assert_eq!(
+            limit_hit(&lua_system),
+            Some(Limit::Time(std::time::Duration::from_millis(50)))
+        );
+    }
+
+    #[test]
+    fn test_memory_limit() {
+        let sandbox = Sandbox {
+            memory_limit: 4 * 1024 * 1024,
+            ..Sandbox::default()
+        };
+        let lua_system = limited(
+            "return function (n) local t = {} while true do t[#t + 1] = {} end end",
+            sandbox,
+        );
+
+        assert_eq!(limit_hit(&lua_system), Some(Limit::Memory(4 * 1024 * 1024)));
+    }
+
+    #[test]
+    fn test_memory_limit_counts_bigints() {
+        let sandbox = Sandbox {
+            memory_limit: 10 * 1024 * 1024,
+            ..Sandbox::default()
+        };
+        let lua_system = limited(
+            "return function (n) local t = {} for i = 1, 400 do t[i] = bigint.new(2) ^ 500000 - i end end",
+            sandbox,
+        );
+
+        assert_eq!(
+            limit_hit(&lua_system),
+            Some(Limit::Memory(10 * 1024 * 1024))
+        );
+    }
+
+    #[test]
+    fn test_limit_while_loading() {
+        let sandbox = Sandbox {
+            instruction_limit: 10_000,
+            ..Sandbox::default()
+        };
+        let system = System::lua("while true do end return tostring", "01").with_sandbox(sandbox);
+
+        let error = LuaSystem::new(&system).unwrap_err();
+
+        assert!(
+            matches!(
+                error,
+                Error::Lua(LuaError::Limit {
+                    position: None,
+                    limit: Limit::Instructions(10_000)
+                })
+            ),
+            "{}",
+            error
+        );
+        assert_eq!(
+            error.to_string(),
+            "lua function exceeded the instruction limit of 10000 while loading"
+        );
+    }
+
+    #[test]
+    fn test_weights_are_computed_once() {
+        let lua_system = LuaSystem::new(&System::lua(
+            "calls = 0 return function (n) calls = calls + 1 return tostring(n) end",
+            "01",
+        ))
+        .unwrap();
+
+        for _ in 0..3 {
+            lua_system.parse("111").unwrap();
+        }
+
+        let calls: i64 = lua_system.lua.globals().get("calls").unwrap();
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn test_long_factoradic_number() {
+        let lua_function = r#"f = function (n) n = BigNum.new(n) one = BigNum.new(1) if n <= one then return tostring(one) else return tostring(n * f(n - one)) end end return f"#;
+        let lua_system = LuaSystem::new(&System::lua(lua_function, "01")).unwrap();
+        let input = "1".repeat(1000);
+
+        let number = lua_system.parse(&input).unwrap();
+
+        let (expected, _) = (2..1000u32).fold((BigInt::from(2), BigInt::from(1)), |(sum, w), n| {
+            let w = w * n;
+            (sum + &w, w)
+        });
+        assert_eq!(number, expected);
+        assert_eq!(lua_system.render(&number).unwrap(), input);
+    }
+
+    #[test]
+    fn test_weight_types() {
+        for (function, weight) in [
+            ("return function (n) return bigint.new(3) ^ n end", 9),
+            ("return function (n) return 1 << n end", 4),
+            ("return function (n) return ' 7 ' end", 7),
+        ] {
+            let lua_system = LuaSystem::new(&System::lua(function, "01")).unwrap();
+
+            assert_eq!(lua_system.calculate_base(2).unwrap(), BigInt::from(weight));
+        }
+        let lua_system = LuaSystem::new(&System::lua("return function (n) return 0.5 end", "01"));
+
+        assert_eq!(
+            lua_system
+                .unwrap()
+                .calculate_base(2)
+                .unwrap_err()
+                .to_string(),
+            "lua function returned \"0.5\" at position 0, expected an integer"
+        );
+    }
+
+    #[test]
+    fn test_inexact_floats_are_rejected() {
+        // Lua's `^` always returns a float, and 10^23 is not a double, so
+        // floats are only read up to 2^53.
+        let lua_system = limited("return function (n) return 10 ^ n end", Sandbox::default());
+
+        assert_eq!(
+            lua_system.calculate_base(15).unwrap(),
+            BigInt::from(10).pow(15)
+        );
+        assert!(matches!(
+            lua_system.calculate_base(23),
+            Err(Error::Lua(LuaError::InvalidWeight { position: 16, .. }))
+        ));
+    }
+
+    #[test]
+    fn test_context() {
+        let system = System::lua(
+            r#"return function (n, context)
+                if n == 0 then return context.params.first end
+                local previous = context.weights[n - 1]
+                local symbols = ""
+                for _, letter in ipairs(context.alphabet) do symbols = symbols .. letter.symbol end
+                assert(context.position == n and context.direction == nil)
+                assert(symbols == "012" and context.min_digit == 0 and context.max_digit == 2)
+                return previous * (context.max_digit + 1) + n
+            end"#,
+            "012",
+        )
+        .with_param("first", serde_json::json!(2));
+        let lua_system = LuaSystem::new(&system).unwrap();
+
+        assert_eq!(lua_system.calculate_base(2).unwrap(), BigInt::from(23));
+    }
+
+    #[test]
+    fn test_params_are_global() {
+        let system = System::lua(
+            "local base = bigint.new(params.base) return function (n) return base ^ n end",
+            "01",
+        )
+        .with_param("base", serde_json::json!(16));
+        let lua_system = LuaSystem::new(&system).unwrap();
+
+        assert_eq!(lua_system.calculate_base(2).unwrap(), BigInt::from(256));
+    }
+
+    #[test]
+    fn test_function_file() {
+        let config = crate::Config::from_file("factoradic.json").unwrap();
+        let lua_system = LuaSystem::new(config.get_source()).unwrap();
+
+        assert_eq!(lua_system.parse("321").unwrap(), BigInt::from(23));
+        assert_eq!(lua_system.render(&BigInt::from(23)).unwrap(), "321");
+    }
+
+    #[test]
+    fn test_errors_name_the_file() {
+        let directory = std::env::temp_dir().join(format!("numberbases-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("broken.lua");
+        fs::write(&path, "return function (n)\n  return n +\nend\n").unwrap();
+
+        let error = LuaSystem::new(&System::lua("", "01").with_function_file(&path)).unwrap_err();
+        let missing = LuaSystem::new(&System::lua("", "01").with_function_file("missing.lua"));
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert!(
+            error
+                .to_string()
+                .contains(&format!("{}:3:", path.display())),
+            "{}",
+            error
+        );
+        assert!(matches!(
+            missing.unwrap_err(),
+            Error::Lua(LuaError::Read { .. })
+        ));
+    }
+
+    #[test]
+    fn test_tracebacks_name_the_chunks() {
+        let lua_system = limited("return function (n) return nil + n end", Sandbox::default());
+
+        let error = lua_system.calculate_base(1).unwrap_err();
+        let source = match &error {
+            Error::Lua(LuaError::Call { source, .. }) => source.to_string(),
+            _ => panic!("{}", error),
+        };
+
+        assert!(source.contains("function:1:"), "{}", source);
+        assert!(source.contains("memoize:"), "{}", source);
+        assert!(!source.contains(".rs:"), "{}", source);
+    }
+
+    #[test]
+    fn test_digit_ranges() {
+        let lua_system = LuaSystem::new(&System::lua(
+            "return function (n) local w = 1 for k = 2, n + 1 do w = w * k end return { weight = w, max_digit = n + 1 } end",
+            "0123",
+        ))
+        .unwrap();
+
+        assert_eq!(lua_system.render(&BigInt::from(23)).unwrap(), "321");
+        assert_eq!(lua_system.render(&BigInt::from(7)).unwrap(), "101");
+        let error = lua_system.parse("1002").unwrap_err();
+        assert!(matches!(
+            error,
+            Error::Parse(ParseError {
+                position: 3,
+                reason: ParseErrorReason::DigitOutOfRange {
+                    value: 2,
+                    min: 0,
+                    max: 1
+                },
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_positional_digit_range() {
+        let lua_system = LuaSystem::new(&System::lua(
+            "return function (n) return { 10 ^ n, 1, 2 } end",
+            "0123",
+        ))
+        .unwrap();
+
+        assert_eq!(lua_system.parse("21").unwrap(), BigInt::from(21));
+        assert!(lua_system.parse("30").is_err());
+        assert!(lua_system.render(&BigInt::from(30)).is_err());
+    }
+
+    #[test]
+    fn test_negative_weights() {
+        let system = System::lua(
+            "return function (n) local w = 1 for _ = 1, n do w = w * 10 end return tostring(-w) end",
+            "01",
+        );
+        let lua_system = LuaSystem::new(&system).unwrap();
+
+        let error = lua_system.render(&BigInt::from(5)).unwrap_err();
+
+        assert_eq!(lua_system.render(&BigInt::from(-11)).unwrap(), "11");
+        assert_eq!(lua_system.render(&BigInt::from(11)).unwrap(), "-11");
+        assert!(matches!(
+            error,
+            Error::Arithmetic(ArithmeticError::Unrepresentable(_))
+        ));
+    }
+
+    #[test]
+    fn test_fractional_weights() {
+        let function =
+            "return function (n) if n < 0 then return '1/' .. (1 << -n) end return 1 << n end";
+        let binary = LuaSystem::new(&System::lua(function, "01")).unwrap();
+        let shifted = LuaSystem::new(&System::lua(function, "01").with_origin(1)).unwrap();
+        let five_quarters = BigRational::new(5.into(), 4.into());
+
+        assert_eq!(
+            binary.parse_rational("0.1").unwrap(),
+            BigRational::new(1.into(), 2.into())
+        );
+        assert_eq!(binary.render_rational(&five_quarters).unwrap(), "1.01");
+        // Position 0 is now the first after the radix point, whose weight 1 is
+        // a valid fraction.
+        assert_eq!(
+            shifted.parse_rational("1.1").unwrap(),
+            BigRational::from(BigInt::from(3))
+        );
+        let error = LuaSystem::new(&System::lua("return function (n) return '1/2' end", "01"))
+            .unwrap()
+            .parse("1")
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "lua function returned \"1/2\" at position 0, expected an integer"
+        );
+    }
+
+    #[test]
+    fn test_alternating_weights() {
+        // Weights (-2)^n write every integer without a sign.
+        let system = System::lua("return function (n) return bigint.new(-2)^n end", "01");
+        let negabinary = LuaSystem::new(&system).unwrap();
+
+        assert_eq!(negabinary.render(&BigInt::from(-3)).unwrap(), "1101");
+        assert_eq!(negabinary.render(&BigInt::from(6)).unwrap(), "11010");
+        assert_eq!(negabinary.parse("11010").unwrap(), BigInt::from(6));
+    }
+}
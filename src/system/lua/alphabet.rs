@@ -0,0 +1,206 @@
+use super::{bigint, describe_value, sandbox::Guard, Sandbox};
+use crate::config::SymbolFunctions;
+use crate::error::LuaError;
+use mlua::prelude::*;
+use mlua::RegistryKey;
+use std::{collections::BTreeSet, rc::Rc};
+
+/// How many digit values from each end of the range, and spread over it, are
+/// checked to read back as themselves when the functions are loaded.
+const ROUND_TRIP_SAMPLE: i128 = 64;
+
+/// Digit symbols written and read by the lua functions of a system's
+/// `symbol_functions`.
+#[derive(Debug)]
+pub struct LuaAlphabet {
+    lua: Lua,
+    guard: Rc<Guard>,
+    symbol: RegistryKey,
+    value: RegistryKey,
+    pattern: Option<String>,
+}
+
+impl LuaAlphabet {
+    /// Loads the functions and checks that symbols of a sample of digit
+    /// values read back as those values.
+    pub fn new(functions: &SymbolFunctions, sandbox: Sandbox) -> Result<Self, LuaError> {
+        let (lua, guard) = sandbox.lua()?;
+        bigint::install(&lua).map_err(LuaError::Library)?;
+        let load = |source: &str, name: &str| -> Result<RegistryKey, LuaError> {
+            guard.run(None, || {
+                lua.load(source)
+                    .set_name(format!("={}", name))
+                    .eval::<LuaFunction>()
+                    .and_then(|function| lua.create_registry_value(function))
+                    .map_err(LuaError::Compile)
+            })
+        };
+        let symbol = load(&functions.symbol, "symbol")?;
+        let value = load(&functions.value, "value")?;
+        let alphabet = Self {
+            lua,
+            guard,
+            symbol,
+            value,
+            pattern: functions.pattern.clone(),
+        };
+        alphabet.check_round_trip(functions.min_value, functions.max_value)?;
+        Ok(alphabet)
+    }
+
+    /// The symbol of digit `value`.
+    pub fn symbol(&self, value: isize) -> Result<String, LuaError> {
+        let result: LuaValue = self.guard.run(Some(value), || {
+            let function: LuaFunction = self
+                .lua
+                .registry_value(&self.symbol)
+                .map_err(LuaError::Library)?;
+            function.call(value).map_err(LuaError::Alphabet)
+        })?;
+        let symbol = match &result {
+            LuaValue::String(symbol) => symbol.to_str().ok().map(str::to_string),
+            LuaValue::Integer(symbol) => Some(symbol.to_string()),
+            _ => None,
+        };
+        symbol
+            .filter(|symbol| !symbol.is_empty())
+            .ok_or_else(|| LuaError::InvalidSymbol {
+                value,
+                symbol: describe_value(&result),
+            })
+    }
+
+    /// The digit value of `symbol`, or `None` if it is not a symbol.
+    pub fn value(&self, symbol: &str) -> Result<Option<isize>, LuaError> {
+        let result: LuaValue = self.guard.run(None, || {
+            let function: LuaFunction = self
+                .lua
+                .registry_value(&self.value)
+                .map_err(LuaError::Library)?;
+            function.call(symbol).map_err(LuaError::Alphabet)
+        })?;
+        Ok(bigint::to_bigint(&result).and_then(|value| isize::try_from(value).ok()))
+    }
+
+    /// Splits `text` into symbols with the pattern, or into characters without
+    /// one. Returns the symbols with the character index each starts at, and
+    /// the index where no symbol matched if the text did not split fully.
+    #[allow(clippy::type_complexity)]
+    pub fn split(&self, text: &str) -> Result<(Vec<(usize, String)>, Option<usize>), LuaError> {
+        let Some(pattern) = &self.pattern else {
+            let symbols = text.chars().map(String::from).enumerate().collect();
+            return Ok((symbols, None));
+        };
+        let find: LuaFunction = self
+            .lua
+            .globals()
+            .get::<_, LuaTable>("string")
+            .and_then(|string| string.get("find"))
+            .map_err(LuaError::Library)?;
+        let mut symbols = Vec::new();
+        let mut start = 0;
+        while start < text.len() {
+            let (first, last): (Option<usize>, Option<usize>) = self.guard.run(None, || {
+                find.call((text, pattern.as_str(), start + 1))
+                    .map_err(LuaError::Alphabet)
+            })?;
+            let end = match (first, last) {
+                (Some(first), Some(last)) if first == start + 1 && last >= first => last,
+                _ => return Ok((symbols, Some(text[..start].chars().count()))),
+            };
+            let Some(symbol) = text.get(start..end) else {
+                return Ok((symbols, Some(text[..start].chars().count())));
+            };
+            symbols.push((text[..start].chars().count(), symbol.to_string()));
+            start = end;
+        }
+        Ok((symbols, None))
+    }
+
+    fn check_round_trip(&self, min_value: isize, max_value: isize) -> Result<(), LuaError> {
+        let (min, max) = (min_value as i128, max_value as i128);
+        let step = ((max - min) / ROUND_TRIP_SAMPLE).max(1);
+        let sample: BTreeSet<i128> = (min..=max)
+            .take(ROUND_TRIP_SAMPLE as usize)
+            .chain((min..=max).rev().take(ROUND_TRIP_SAMPLE as usize))
+            .chain((min..=max).step_by(step as usize))
+            .collect();
+        for value in sample {
+            let value = value as isize;
+            let symbol = self.symbol(value)?;
+            let (symbols, rest) = self.split(&symbol)?;
+            let splits_whole = rest.is_none() && symbols.len() == 1;
+            if !splits_whole || self.value(&symbol)? != Some(value) {
+                return Err(LuaError::RoundTrip { value, symbol });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::lua::Limit;
+
+    fn angle_brackets() -> SymbolFunctions {
+        SymbolFunctions {
+            symbol: "return function (v) return '<' .. v .. '>' end".to_string(),
+            value: "return function (s) return tonumber(s:match('^<(%d+)>$')) end".to_string(),
+            pattern: Some("<%d+>".to_string()),
+            min_value: 0,
+            max_value: 999,
+        }
+    }
+
+    #[test]
+    fn test_generated_symbols() {
+        let alphabet = LuaAlphabet::new(&angle_brackets(), Sandbox::default()).unwrap();
+
+        assert_eq!(alphabet.symbol(123).unwrap(), "<123>");
+        assert_eq!(alphabet.value("<7>").unwrap(), Some(7));
+        assert_eq!(alphabet.value("7").unwrap(), None);
+        assert_eq!(
+            alphabet.split("<1><23>").unwrap(),
+            (vec![(0, "<1>".to_string()), (3, "<23>".to_string())], None)
+        );
+        assert_eq!(
+            alphabet.split("<1>x<2>").unwrap(),
+            (vec![(0, "<1>".to_string())], Some(3))
+        );
+    }
+
+    #[test]
+    fn test_round_trip_is_checked() {
+        let functions = SymbolFunctions {
+            value: "return function (s) return 0 end".to_string(),
+            ..angle_brackets()
+        };
+
+        let error = LuaAlphabet::new(&functions, Sandbox::default()).unwrap_err();
+
+        assert!(matches!(error, LuaError::RoundTrip { value: 1, .. }));
+    }
+
+    #[test]
+    fn test_limits_are_enforced() {
+        let functions = SymbolFunctions {
+            symbol: "return function (v) while true do end end".to_string(),
+            ..angle_brackets()
+        };
+        let sandbox = Sandbox {
+            instruction_limit: 100_000,
+            ..Sandbox::default()
+        };
+
+        let error = LuaAlphabet::new(&functions, sandbox).unwrap_err();
+
+        assert!(matches!(
+            error,
+            LuaError::Limit {
+                position: Some(0),
+                limit: Limit::Instructions(100_000),
+            }
+        ));
+    }
+}
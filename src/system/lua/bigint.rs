@@ -0,0 +1,442 @@
+use super::sandbox::Guard;
+use crate::config::parse_weight_str;
+use mlua::prelude::*;
+use mlua::{MetaMethod, UserData, UserDataMethods};
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{FromPrimitive, Signed, ToPrimitive, Zero};
+use std::rc::Rc;
+
+/// Largest result, in bits, of an operation on big integers. It is checked
+/// before computing the result, as a single operation runs in one lua
+/// instruction that the sandbox cannot interrupt.
+const MAX_BITS: u64 = 1 << 20;
+
+/// Largest float magnitude read as an integer, 2^53. Larger floats, such as
+/// those of lua's `^`, may have been rounded.
+const MAX_EXACT_FLOAT: f64 = 9_007_199_254_740_992.0;
+
+/// Lua code defining the `BigNum` table on top of `bigint`, so functions
+/// written for the old pure-lua BigNum library keep working. The chunk gets
+/// a function assigning a value into an existing bigint, for the forms that
+/// write their result into an argument. `BigNum.put` and `BigNum.mt` worked
+/// on the old digit tables and raise an error.
+const LUA_BIGNUM_SHIM: &str = r#"
+local assign = ...
+
+local function into(name, target, value)
+    if type(target) ~= "userdata" then
+        error("BigNum." .. name .. ": result argument is not a BigNum", 3)
+    end
+    assign(target, value)
+    return 0
+end
+
+local function unsupported(name)
+    return function ()
+        error("BigNum." .. name .. " is not supported, use bigint arithmetic instead", 2)
+    end
+end
+
+local function binary(name, op)
+    return function (a, b, c)
+        local result = op(bigint.new(a), bigint.new(b))
+        if c == nil then return result end
+        return into(name, c, result)
+    end
+end
+
+BigNum = {
+    new = function (num) return bigint.new(num or 0) end,
+    add = binary("add", function (a, b) return a + b end),
+    sub = binary("sub", function (a, b) return a - b end),
+    mul = binary("mul", function (a, b) return a * b end),
+    div = function (a, b, q, r)
+        a, b = bigint.new(a), bigint.new(b)
+        if q == nil and r == nil then return a // b end
+        into("div", q, a // b)
+        if r ~= nil then into("div", r, a % b) end
+        return 0
+    end,
+    pow = function (a, b) return bigint.new(a) ^ b end,
+    gcd = bigint.gcd,
+    eq = function (a, b) return bigint.new(a) == bigint.new(b) end,
+    lt = function (a, b) return bigint.new(a) < bigint.new(b) end,
+    le = function (a, b) return bigint.new(a) <= bigint.new(b) end,
+    compare = function (a, b)
+        a, b = bigint.new(a), bigint.new(b)
+        if a < b then return -1 elseif a > b then return 1 else return 0 end
+    end,
+    compareAbs = function (a, b)
+        return BigNum.compare(bigint.new(a):abs(), bigint.new(b):abs())
+    end,
+    copy = function (a, b)
+        if a == nil or b == nil then error("BigNum.copy: parameter nil", 2) end
+        return into("copy", b, bigint.new(a))
+    end,
+    change = function (b, num)
+        if b == nil then error("BigNum.change: parameter nil", 2) end
+        return into("change", b, bigint.new(num or 0))
+    end,
+    put = unsupported("put"),
+    mt = setmetatable({}, {
+        __index = unsupported("mt"),
+        __newindex = unsupported("mt"),
+    }),
+}
+BigNum.exp = BigNum.pow
+BigNum.mmc = BigNum.gcd
+"#;
+
+/// A `num_bigint::BigInt` as lua userdata. Operators accept big integers,
+/// lua integers, integral floats up to 2^53 and decimal strings on either
+/// side.
+///
+/// Big integers live outside the lua allocator, so while one is alive its
+/// bytes are counted against the memory limit of the sandbox, if the lua
+/// state has one.
+#[derive(Debug)]
+pub(super) struct LuaBigInt {
+    pub value: BigInt,
+    _charge: Charge,
+}
+
+/// The bytes of a big integer counted against a sandbox, released on drop.
+#[derive(Debug)]
+struct Charge(Option<(Rc<Guard>, usize)>);
+
+impl Drop for Charge {
+    fn drop(&mut self) {
+        if let Some((guard, bytes)) = &self.0 {
+            guard.release(*bytes);
+        }
+    }
+}
+
+impl LuaBigInt {
+    /// `n` as userdata of `lua`, charged to its sandbox. Runs a garbage
+    /// collection first if `n` does not fit, as dropped big integers are
+    /// only released when they are collected.
+    pub(super) fn new(lua: &Lua, n: BigInt) -> LuaResult<Self> {
+        let Some(guard) = lua
+            .app_data_ref::<Rc<Guard>>()
+            .map(|guard| Rc::clone(&guard))
+        else {
+            return Ok(Self {
+                value: n,
+                _charge: Charge(None),
+            });
+        };
+        let bytes = std::mem::size_of::<BigInt>() + (n.bits() as usize).div_ceil(64) * 8;
+        if !guard.fits(lua.used_memory(), bytes) {
+            lua.gc_collect()?;
+        }
+        guard.charge(lua.used_memory(), bytes)?;
+        Ok(Self {
+            value: n,
+            _charge: Charge(Some((guard, bytes))),
+        })
+    }
+}
+
+/// Installs the `bigint` library and the `BigNum` shim as globals.
+pub(super) fn install(lua: &Lua) -> LuaResult<()> {
+    let library = lua.create_table()?;
+    library.set("new", lua.create_function(|_, n: LuaBigInt| Ok(n))?)?;
+    library.set(
+        "gcd",
+        lua.create_function(|lua, (a, b): (LuaBigInt, LuaBigInt)| gcd(lua, &a.value, &b.value))?,
+    )?;
+    library.set(
+        "isqrt",
+        lua.create_function(|lua, n: LuaBigInt| isqrt(lua, &n.value))?,
+    )?;
+    lua.globals().set("bigint", library)?;
+    let assign = lua.create_function(|_, (target, value): (LuaAnyUserData, LuaBigInt)| {
+        *target.borrow_mut::<LuaBigInt>()? = value;
+        Ok(())
+    })?;
+    lua.load(LUA_BIGNUM_SHIM).set_name("=BigNum").call(assign)
+}
+
+/// The integer `value` stands for, if any. Floats above 2^53 are not read,
+/// as they may not be exact.
+pub(super) fn to_bigint(value: &LuaValue) -> Option<BigInt> {
+    match value {
+        LuaValue::Integer(n) => Some(BigInt::from(*n)),
+        LuaValue::Number(n) if n.fract() == 0.0 && n.abs() <= MAX_EXACT_FLOAT => {
+            BigInt::from_f64(*n)
+        }
+        LuaValue::String(s) => s.to_str().ok()?.trim().parse().ok(),
+        LuaValue::UserData(data) => data.borrow::<LuaBigInt>().ok().map(|n| n.value.clone()),
+        _ => None,
+    }
+}
+
+/// The integer or fraction `value` stands for, if any. Fractions are strings
+/// such as `"1/6"`.
+pub(super) fn to_rational(value: &LuaValue) -> Option<BigRational> {
+    match value {
+        LuaValue::String(s) => parse_weight_str(s.to_str().ok()?),
+        value => to_bigint(value).map(BigRational::from),
+    }
+}
+
+impl<'lua> FromLua<'lua> for LuaBigInt {
+    fn from_lua(value: LuaValue<'lua>, lua: &'lua Lua) -> LuaResult<Self> {
+        let n = to_bigint(&value).ok_or_else(|| LuaError::FromLuaConversionError {
+            from: value.type_name(),
+            to: "bigint",
+            message: Some("expected an integer".to_string()),
+        })?;
+        LuaBigInt::new(lua, n)
+    }
+}
+
+fn arithmetic_error(message: &str) -> LuaError {
+    LuaError::RuntimeError(format!("bigint: {}", message))
+}
+
+/// The result of `operation`, which has at most `bits` bits, unless that is
+/// more than [`MAX_BITS`].
+fn bounded(lua: &Lua, bits: u64, operation: impl FnOnce() -> BigInt) -> LuaResult<LuaBigInt> {
+    if bits > MAX_BITS {
+        return Err(arithmetic_error("result is too large"));
+    }
+    LuaBigInt::new(lua, operation())
+}
+
+/// The bits of a sum or a bitwise operation of `a` and `b`.
+fn wider(a: &BigInt, b: &BigInt) -> u64 {
+    a.bits().max(b.bits()) + 1
+}
+
+fn nonzero(divisor: &BigInt) -> LuaResult<&BigInt> {
+    if divisor.is_zero() {
+        return Err(arithmetic_error("division by zero"));
+    }
+    Ok(divisor)
+}
+
+fn gcd(lua: &Lua, a: &BigInt, b: &BigInt) -> LuaResult<LuaBigInt> {
+    bounded(lua, a.bits().max(b.bits()), || a.gcd(b))
+}
+
+fn isqrt(lua: &Lua, n: &BigInt) -> LuaResult<LuaBigInt> {
+    if n.is_negative() {
+        return Err(arithmetic_error("square root of a negative number"));
+    }
+    bounded(lua, n.bits(), || n.sqrt())
+}
+
+fn pow(lua: &Lua, base: &BigInt, exponent: &BigInt) -> LuaResult<LuaBigInt> {
+    if exponent.is_negative() {
+        return Err(arithmetic_error("negative exponent"));
+    }
+    if base.magnitude() <= &1u32.into() {
+        // Only whether the exponent is zero, even or odd matters.
+        let exponent = match (exponent.is_zero(), exponent.is_even()) {
+            (true, _) => 0,
+            (false, true) => 2,
+            (false, false) => 1,
+        };
+        return bounded(lua, 1, || base.pow(exponent));
+    }
+    let exponent = exponent
+        .to_u32()
+        .ok_or_else(|| arithmetic_error("result is too large"))?;
+    bounded(lua, base.bits().saturating_mul(u64::from(exponent)), || {
+        base.pow(exponent)
+    })
+}
+
+fn shift(lua: &Lua, n: &BigInt, by: &BigInt) -> LuaResult<LuaBigInt> {
+    let by = by
+        .to_i64()
+        .ok_or_else(|| arithmetic_error("shift is too large"))?;
+    match by < 0 {
+        true => bounded(lua, n.bits(), || n >> by.unsigned_abs()),
+        false => bounded(lua, n.bits().saturating_add(by as u64), || n << by as u64),
+    }
+}
+
+impl UserData for LuaBigInt {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        type Pair = (LuaBigInt, LuaBigInt);
+
+        methods.add_meta_function(MetaMethod::Add, |lua, (a, b): Pair| {
+            bounded(lua, wider(&a.value, &b.value), || a.value + b.value)
+        });
+        methods.add_meta_function(MetaMethod::Sub, |lua, (a, b): Pair| {
+            bounded(lua, wider(&a.value, &b.value), || a.value - b.value)
+        });
+        methods.add_meta_function(MetaMethod::Mul, |lua, (a, b): Pair| {
+            bounded(lua, a.value.bits() + b.value.bits(), || a.value * b.value)
+        });
+        // `/` floors like `//`, as BigNum division did not produce fractions.
+        for method in [MetaMethod::Div, MetaMethod::IDiv] {
+            methods.add_meta_function(method, |lua, (a, b): Pair| {
+                let divisor = nonzero(&b.value)?;
+                bounded(lua, a.value.bits() + 1, || a.value.div_floor(divisor))
+            });
+        }
+        methods.add_meta_function(MetaMethod::Mod, |lua, (a, b): Pair| {
+            let divisor = nonzero(&b.value)?;
+            bounded(lua, b.value.bits(), || a.value.mod_floor(divisor))
+        });
+        methods.add_meta_function(MetaMethod::Pow, |lua, (a, b): Pair| {
+            pow(lua, &a.value, &b.value)
+        });
+        methods.add_meta_function(MetaMethod::Unm, |lua, a: LuaBigInt| {
+            bounded(lua, a.value.bits(), || -a.value)
+        });
+
+        methods.add_meta_function(MetaMethod::Eq, |_, (a, b): Pair| Ok(a.value == b.value));
+        methods.add_meta_function(MetaMethod::Lt, |_, (a, b): Pair| Ok(a.value < b.value));
+        methods.add_meta_function(MetaMethod::Le, |_, (a, b): Pair| Ok(a.value <= b.value));
+
+        methods.add_meta_function(MetaMethod::BAnd, |lua, (a, b): Pair| {
+            bounded(lua, wider(&a.value, &b.value), || a.value & b.value)
+        });
+        methods.add_meta_function(MetaMethod::BOr, |lua, (a, b): Pair| {
+            bounded(lua, wider(&a.value, &b.value), || a.value | b.value)
+        });
+        methods.add_meta_function(MetaMethod::BXor, |lua, (a, b): Pair| {
+            bounded(lua, wider(&a.value, &b.value), || a.value ^ b.value)
+        });
+        methods.add_meta_function(MetaMethod::BNot, |lua, a: LuaBigInt| {
+            bounded(lua, a.value.bits() + 1, || !a.value)
+        });
+        methods.add_meta_function(MetaMethod::Shl, |lua, (a, b): Pair| {
+            shift(lua, &a.value, &b.value)
+        });
+        methods.add_meta_function(MetaMethod::Shr, |lua, (a, b): Pair| {
+            shift(lua, &a.value, &-&b.value)
+        });
+
+        methods.add_meta_method(MetaMethod::ToString, |_, n, ()| Ok(n.value.to_string()));
+
+        methods.add_method("gcd", |lua, n, other: LuaBigInt| {
+            gcd(lua, &n.value, &other.value)
+        });
+        methods.add_method("isqrt", |lua, n, ()| isqrt(lua, &n.value));
+        methods.add_method("abs", |lua, n, ()| {
+            bounded(lua, n.value.bits(), || n.value.abs())
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(code: &str) -> String {
+        let lua = Lua::new();
+        install(&lua).unwrap();
+        lua.load(code).eval::<String>().unwrap()
+    }
+
+    #[test]
+    fn test_operators() {
+        assert_eq!(
+            eval("return tostring(bigint.new(2) ^ 100)"),
+            "1267650600228229401496703205376"
+        );
+        assert_eq!(eval("return tostring(-7 // bigint.new(2))"), "-4");
+        assert_eq!(eval("return tostring(bigint.new(-7) % 2)"), "1");
+        assert_eq!(
+            eval("return tostring(bigint.new('12') * 3 - 1 + 10^6)"),
+            "1000035"
+        );
+        assert_eq!(eval("return tostring(bigint.new(6) & 3 | 8 ~ 1)"), "11");
+        assert_eq!(eval("return tostring((bigint.new(1) << 70) >> 68)"), "4");
+        assert_eq!(eval("return tostring(~bigint.new(0))"), "-1");
+        assert_eq!(
+            eval("return tostring(bigint.new(-1) ^ 3) .. tostring(bigint.new(0) ^ 0) .. tostring(bigint.new(0) ^ 2)"),
+            "-110"
+        );
+        assert_eq!(
+            eval("return tostring(bigint.new(1) < 2 and bigint.new(3) == bigint.new('3'))"),
+            "true"
+        );
+    }
+
+    #[test]
+    fn test_functions() {
+        assert_eq!(eval("return tostring(bigint.gcd(12, 18))"), "6");
+        assert_eq!(
+            eval("return tostring(bigint.isqrt(bigint.new(10) ^ 40))"),
+            "100000000000000000000"
+        );
+        assert_eq!(eval("return tostring(bigint.new(-5):abs():gcd(15))"), "5");
+    }
+
+    #[test]
+    fn test_bignum_shim() {
+        assert_eq!(
+            eval(
+                "local n = BigNum.new(5) local one = BigNum.new(1) return tostring(n * (n - one))"
+            ),
+            "20"
+        );
+        assert_eq!(
+            eval("return tostring(BigNum.compare(2, BigNum.new(3)))"),
+            "-1"
+        );
+    }
+
+    #[test]
+    fn test_bignum_output_arguments() {
+        let old = r#"
+            local a, b, c = BigNum.new(17), BigNum.new(5), BigNum.new(0)
+            local q, r = BigNum.new(), BigNum.new()
+            local results = {}
+            BigNum.add(a, b, c) results[#results + 1] = tostring(c)
+            BigNum.sub(a, b, c) results[#results + 1] = tostring(c)
+            BigNum.mul(a, b, c) results[#results + 1] = tostring(c)
+            BigNum.div(a, b, q, r)
+            results[#results + 1] = tostring(q) .. "r" .. tostring(r)
+            BigNum.copy(a, c) results[#results + 1] = tostring(c)
+            BigNum.change(c, "123") results[#results + 1] = tostring(c)
+            BigNum.mul(c, c, c) results[#results + 1] = tostring(c)
+            return table.concat(results, " ")
+        "#;
+        assert_eq!(eval(old), "22 12 85 3r2 17 123 15129");
+
+        let lua = Lua::new();
+        install(&lua).unwrap();
+        for (code, message) in [
+            (
+                "BigNum.add(1, 2, {})",
+                "BigNum.add: result argument is not a BigNum",
+            ),
+            ("BigNum.copy(1)", "BigNum.copy: parameter nil"),
+            (
+                "BigNum.put(BigNum.new(1), 2, 0)",
+                "BigNum.put is not supported",
+            ),
+            ("return BigNum.mt.add", "BigNum.mt is not supported"),
+        ] {
+            let error = lua.load(code).exec().unwrap_err().to_string();
+            assert!(error.contains(message), "{code}: {error}");
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let lua = Lua::new();
+        install(&lua).unwrap();
+        for code in [
+            "return bigint.new(1) // 0",
+            "return bigint.new(10) ^ (1 << 40)",
+            "local n = bigint.new(2) ^ (1 << 19) return n * n",
+            "local n = bigint.new(1) << ((1 << 20) - 1) return n + n",
+            "local n = bigint.new(3) while true do n = n * n end",
+            "return bigint.isqrt(-1)",
+            "return bigint.new('x')",
+        ] {
+            assert!(lua.load(code).exec().is_err(), "{}", code);
+        }
+    }
+}
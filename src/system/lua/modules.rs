@@ -0,0 +1,70 @@
+use super::read_chunk;
+use crate::error::LuaError;
+use mlua::prelude::*;
+use std::{collections::BTreeMap, path::PathBuf};
+
+/// A `package` table and a `require` that only look in `package.preload`,
+/// as the sandbox cannot search the file system for modules.
+const LUA_REQUIRE: &str = r#"
+package = { preload = {}, loaded = {} }
+
+package.preload.bigint = function () return bigint end
+package.preload.BigNum = function () return BigNum end
+
+function require (name)
+    local module = package.loaded[name]
+    if module ~= nil then
+        return module
+    end
+    local loader = package.preload[name]
+    if loader == nil then
+        error("module '" .. tostring(name) .. "' not found in package.preload", 2)
+    end
+    module = loader(name, ":preload:")
+    if module == nil then
+        module = true
+    end
+    package.loaded[name] = module
+    return module
+end
+"#;
+
+/// Installs `require` with the bundled libraries, `modules`, which map names
+/// to lua source, and `libraries`, lua files required by their name without
+/// extension.
+pub(super) fn install(
+    lua: &Lua,
+    modules: &BTreeMap<String, String>,
+    libraries: &[PathBuf],
+) -> Result<(), LuaError> {
+    lua.load(LUA_REQUIRE)
+        .set_name("=require")
+        .exec()
+        .map_err(LuaError::Library)?;
+    let preload =
+        || -> LuaResult<LuaTable> { lua.globals().get::<_, LuaTable>("package")?.get("preload") };
+    let preload = preload().map_err(LuaError::Library)?;
+    for (name, source) in modules {
+        let loader = lua
+            .load(source)
+            .set_name(format!("={}", name))
+            .into_function()
+            .map_err(LuaError::Compile)?;
+        preload
+            .set(name.as_str(), loader)
+            .map_err(LuaError::Library)?;
+    }
+    for path in libraries {
+        let (source, chunk_name) = read_chunk(path)?;
+        let name = path.file_stem().unwrap_or_default().to_string_lossy();
+        let loader = lua
+            .load(&source)
+            .set_name(chunk_name)
+            .into_function()
+            .map_err(LuaError::Compile)?;
+        preload
+            .set(name.as_ref(), loader)
+            .map_err(LuaError::Library)?;
+    }
+    Ok(())
+}
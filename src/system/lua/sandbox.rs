@@ -0,0 +1,190 @@
+use crate::error::LuaError;
+use mlua::{HookTriggers, Lua, LuaOptions, StdLib};
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::Cell,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+/// Libraries that cannot reach outside the lua state. `coroutine` is left out
+/// because the instruction hook does not follow code into coroutines.
+fn safe_libraries() -> StdLib {
+    StdLib::MATH | StdLib::STRING | StdLib::TABLE | StdLib::UTF8
+}
+
+/// Base library functions that load code from files or bytecode.
+const UNSAFE_GLOBALS: [&str; 3] = ["dofile", "loadfile", "load"];
+
+/// How often the hook checks the limits, in lua VM instructions.
+const HOOK_INTERVAL: u32 = 1000;
+
+/// Limits on a lua weight function. The instruction and time limits apply to
+/// each evaluation of a weight, the memory limit to the whole lua state
+/// including its big integers. A limit of 0 disables it.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct Sandbox {
+    pub instruction_limit: u64,
+    /// In bytes.
+    pub memory_limit: usize,
+    pub timeout_ms: u64,
+}
+
+impl Default for Sandbox {
+    fn default() -> Self {
+        Self {
+            instruction_limit: 100_000_000,
+            memory_limit: 256 * 1024 * 1024,
+            timeout_ms: 10_000,
+        }
+    }
+}
+
+/// The limit a lua function ran into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Instructions(u64),
+    Memory(usize),
+    Time(Duration),
+}
+
+/// Tracks the work done by the evaluation currently running in a lua state.
+#[derive(Debug)]
+pub(super) struct Guard {
+    sandbox: Sandbox,
+    instructions: Cell<u64>,
+    deadline: Cell<Option<Instant>>,
+    exceeded: Cell<Option<Limit>>,
+    /// Bytes of the big integers alive in the lua state, which live outside
+    /// the lua allocator.
+    bigints: Cell<usize>,
+}
+
+impl Sandbox {
+    /// Creates a lua state with only safe libraries and these limits installed.
+    pub(super) fn lua(&self) -> Result<(Lua, Rc<Guard>), LuaError> {
+        let lua =
+            Lua::new_with(safe_libraries(), LuaOptions::default()).map_err(LuaError::Library)?;
+        {
+            let globals = lua.globals();
+            for name in UNSAFE_GLOBALS {
+                globals.raw_remove(name).map_err(LuaError::Library)?;
+            }
+        }
+        if self.memory_limit > 0 {
+            lua.set_memory_limit(self.memory_limit)
+                .map_err(LuaError::Library)?;
+        }
+
+        let guard = Rc::new(Guard {
+            sandbox: *self,
+            instructions: Cell::new(0),
+            deadline: Cell::new(None),
+            exceeded: Cell::new(None),
+            bigints: Cell::new(0),
+        });
+        lua.set_app_data(Rc::clone(&guard));
+        let hook_guard = Rc::clone(&guard);
+        lua.set_hook(
+            HookTriggers::new().every_nth_instruction(HOOK_INTERVAL),
+            move |_, _| hook_guard.check(),
+        );
+        Ok((lua, guard))
+    }
+}
+
+impl Guard {
+    /// Runs one evaluation with fresh instruction and time budgets, turning
+    /// errors caused by a limit into [`LuaError::Limit`]. `position` is the
+    /// position evaluated, or `None` while loading the function.
+    pub(super) fn run<T>(
+        &self,
+        position: Option<isize>,
+        evaluate: impl FnOnce() -> Result<T, LuaError>,
+    ) -> Result<T, LuaError> {
+        self.reset();
+        evaluate().map_err(|error| {
+            let memory_error = match &error {
+                LuaError::Compile(source)
+                | LuaError::Alphabet(source)
+                | LuaError::Call { source, .. } => is_memory_error(source),
+                _ => false,
+            };
+            match self.exceeded.take() {
+                Some(limit) => LuaError::Limit { position, limit },
+                None if memory_error => LuaError::Limit {
+                    position,
+                    limit: Limit::Memory(self.sandbox.memory_limit),
+                },
+                None => error,
+            }
+        })
+    }
+
+    /// Starts fresh instruction and time budgets.
+    pub(super) fn reset(&self) {
+        self.instructions.set(0);
+        self.exceeded.set(None);
+        let timeout = self.sandbox.timeout_ms;
+        self.deadline
+            .set((timeout > 0).then(|| Instant::now() + Duration::from_millis(timeout)));
+    }
+
+    /// Whether `bytes` more of big integers fit in the memory limit next to
+    /// the `used` bytes of the lua allocator.
+    pub(super) fn fits(&self, used: usize, bytes: usize) -> bool {
+        let limit = self.sandbox.memory_limit;
+        limit == 0 || used + self.bigints.get() + bytes <= limit
+    }
+
+    /// Counts `bytes` of a new big integer against the memory limit.
+    pub(super) fn charge(&self, used: usize, bytes: usize) -> mlua::Result<()> {
+        if !self.fits(used, bytes) {
+            return self.exceed(Limit::Memory(self.sandbox.memory_limit));
+        }
+        self.bigints.set(self.bigints.get() + bytes);
+        Ok(())
+    }
+
+    /// Stops counting `bytes` of a dropped big integer.
+    pub(super) fn release(&self, bytes: usize) {
+        self.bigints.set(self.bigints.get() - bytes);
+    }
+
+    fn check(&self) -> mlua::Result<()> {
+        let instructions = self.instructions.get() + u64::from(HOOK_INTERVAL);
+        self.instructions.set(instructions);
+        let limit = self.sandbox.instruction_limit;
+        if limit > 0 && instructions > limit {
+            return self.exceed(Limit::Instructions(limit));
+        }
+        if self.deadline.get().is_some_and(|d| Instant::now() > d) {
+            return self.exceed(Limit::Time(Duration::from_millis(self.sandbox.timeout_ms)));
+        }
+        Ok(())
+    }
+
+    fn exceed(&self, limit: Limit) -> mlua::Result<()> {
+        self.exceeded.set(Some(limit));
+        Err(mlua::Error::RuntimeError(format!("{} exceeded", limit)))
+    }
+}
+
+fn is_memory_error(error: &mlua::Error) -> bool {
+    match error {
+        mlua::Error::MemoryError(_) => true,
+        mlua::Error::CallbackError { cause, .. } => is_memory_error(cause),
+        _ => false,
+    }
+}
+
+impl std::fmt::Display for Limit {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Limit::Instructions(limit) => write!(f, "instruction limit of {}", limit),
+            Limit::Memory(limit) => write!(f, "memory limit of {} bytes", limit),
+            Limit::Time(limit) => write!(f, "time limit of {:?}", limit),
+        }
+    }
+}
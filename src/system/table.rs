@@ -0,0 +1,316 @@
+use super::{
+    decompose_rational, integer, sum_ranges, Decomposition, Digit, FractionPlace, Numeral,
+    NumeralSystem, Place, Symbols,
+};
+use crate::config::{parse_weight_str, System};
+use crate::error::{ArithmeticError, ConfigError, ParseError, ParseErrorReason, Result};
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use std::{fs, path::Path};
+
+/// A positional system whose weights are listed position by position, in the
+/// config or in a weights file. The weight at the origin's index is that of
+/// the last digit before the radix point, and those before it, which may be
+/// fractions, belong to the digits after the point. Numbers needing more
+/// positions than the table has cannot be written.
+#[derive(Debug)]
+pub struct TableSystem {
+    symbols: Symbols,
+    decomposition: Decomposition,
+    places: Vec<Place>,
+    /// The places after the radix point, from the point on.
+    fractions: Vec<FractionPlace>,
+    /// The smallest and the largest number the table can write.
+    lowest: BigRational,
+    highest: BigRational,
+}
+
+impl TableSystem {
+    pub fn new(system: &System) -> Result<Self> {
+        let mut weights = match system.get_weights_file() {
+            Some(path) => read_weights(path)?,
+            None => system.get_weights().to_vec(),
+        };
+        let origin = usize::try_from(system.get_origin())
+            .ok()
+            .filter(|&origin| origin <= weights.len())
+            .ok_or(ConfigError::OriginOutsideTable {
+                origin: system.get_origin(),
+                positions: weights.len(),
+            })?;
+        let symbols = Symbols::new(system)?;
+        let digit_range = |position| symbols.digit_range(position).unwrap_or_default();
+        let integers = weights.split_off(origin);
+        let mut places = Vec::with_capacity(integers.len());
+        for (position, weight) in integers.into_iter().enumerate() {
+            if !weight.is_integer() {
+                return Err(ConfigError::FractionalWeight { position, weight }.into());
+            }
+            let (min_digit, max_digit) = digit_range(position as isize);
+            places.push(Place {
+                weight: weight.to_integer(),
+                min_digit,
+                max_digit,
+            });
+        }
+        let fractions: Vec<FractionPlace> = weights
+            .into_iter()
+            .rev()
+            .enumerate()
+            .map(|(index, weight)| {
+                let (min_digit, max_digit) = digit_range(-1 - index as isize);
+                FractionPlace {
+                    weight,
+                    min_digit,
+                    max_digit,
+                }
+            })
+            .collect();
+        let (mut low, mut high) = sum_ranges(&places);
+        let mut lowest = BigRational::from(low.pop().unwrap_or_default());
+        let mut highest = BigRational::from(high.pop().unwrap_or_default());
+        for place in &fractions {
+            let first = &place.weight * BigInt::from(place.min_digit);
+            let last = &place.weight * BigInt::from(place.max_digit);
+            lowest += first.clone().min(last.clone());
+            highest += first.max(last);
+        }
+        Ok(Self {
+            symbols,
+            decomposition: system.get_decomposition(),
+            places,
+            fractions,
+            lowest,
+            highest,
+        })
+    }
+
+    fn numeral(&self, number: &BigRational) -> Result<Numeral> {
+        if number < &self.lowest || number > &self.highest {
+            return Err(ArithmeticError::TableExhausted {
+                number: number.clone(),
+                positions: self.places.len() + self.fractions.len(),
+            }
+            .into());
+        }
+        decompose_rational(
+            number,
+            self.decomposition,
+            |position| Ok(self.places.get(position).cloned()),
+            |index| Ok(self.fractions.get(index - 1).cloned()),
+        )
+    }
+}
+
+/// Reads one weight per line. Blank lines and lines starting with `#` are
+/// skipped, and a weight may be wrapped in parentheses, as in `(05)`.
+fn read_weights(path: &Path) -> std::result::Result<Vec<BigRational>, ConfigError> {
+    let text = fs::read_to_string(path).map_err(|e| ConfigError::UnreadableWeights {
+        path: path.to_path_buf(),
+        message: e.to_string(),
+    })?;
+    let mut weights = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let weight = line
+            .strip_prefix('(')
+            .and_then(|inner| inner.strip_suffix(')'))
+            .unwrap_or(line);
+        let weight = parse_weight_str(weight).ok_or_else(|| ConfigError::InvalidWeightLine {
+            path: path.to_path_buf(),
+            line: index + 1,
+            text: line.to_string(),
+        })?;
+        weights.push(weight);
+    }
+    Ok(weights)
+}
+
+impl NumeralSystem for TableSystem {
+    fn parse(&self, input: &str) -> Result<BigInt> {
+        integer(self.parse_rational(input)?)
+    }
+
+    fn render(&self, number: &BigInt) -> Result<String> {
+        self.render_rational(&BigRational::from(number.clone()))
+    }
+
+    /// Rejects numbers with more digits than the table has positions.
+    fn parse_rational(&self, input: &str) -> Result<BigRational> {
+        let numeral = self.symbols.read_numeral(input)?;
+        let error = |position, reason| ParseError {
+            input: input.to_string(),
+            position,
+            reason,
+        };
+        if numeral.integer.len() > self.places.len() {
+            let reason = ParseErrorReason::TooManyDigits(self.places.len());
+            return Err(error(usize::from(numeral.negative), reason).into());
+        }
+        if numeral.fraction.len() > self.fractions.len() {
+            let first = -1 - self.fractions.len() as isize;
+            let reason = ParseErrorReason::TooManyFractionDigits(self.fractions.len());
+            return Err(error(numeral.offset(first), reason).into());
+        }
+        let integer: BigInt = numeral
+            .integer
+            .iter()
+            .zip(&self.places)
+            .map(|(&value, place)| &place.weight * value)
+            .sum();
+        let mut number = BigRational::from(integer);
+        for (&value, place) in numeral.fraction.iter().zip(&self.fractions) {
+            number += &place.weight * BigInt::from(value);
+        }
+        Ok(if numeral.negative { -number } else { number })
+    }
+
+    /// Weights may be negative or alternate in sign. A leading `-` is only
+    /// written for numbers the weights cannot reach.
+    fn render_rational(&self, number: &BigRational) -> Result<String> {
+        self.symbols
+            .write_number(number, |number| self.numeral(number))
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "weights from a table of {} positions with digits {}",
+            self.places.len(),
+            self.symbols.describe()
+        )
+    }
+
+    fn place_at(&self, position: usize) -> Result<Option<Place>> {
+        Ok(self.places.get(position).cloned())
+    }
+
+    fn fraction_place_at(&self, position: usize) -> Result<Option<FractionPlace>> {
+        Ok(position
+            .checked_sub(1)
+            .and_then(|index| self.fractions.get(index))
+            .cloned())
+    }
+
+    fn digits(&self, input: &str) -> Result<Option<Vec<Digit>>> {
+        self.symbols.weighed_digits(input, self).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+
+    fn table(weights: &[i64], symbols: &str) -> TableSystem {
+        let system = System::table(weights.iter().map(|&w| BigInt::from(w)), symbols);
+        TableSystem::new(&system).unwrap()
+    }
+
+    #[test]
+    fn test_word_layout() {
+        // A 12 bit word written as three 4 bit fields.
+        let word = table(&[1, 16, 256], "0123456789ABCDEF");
+
+        assert_eq!(word.parse("A5").unwrap(), BigInt::from(165));
+        assert_eq!(word.render(&BigInt::from(4095)).unwrap(), "FFF");
+        assert_eq!(word.render(&BigInt::from(256)).unwrap(), "100");
+    }
+
+    #[test]
+    fn test_running_past_the_table() {
+        let word = table(&[1, 2, 4], "01");
+
+        let parse_error = word.parse("1000").unwrap_err();
+        let render_error = word.render(&BigInt::from(8)).unwrap_err();
+
+        assert!(matches!(
+            parse_error,
+            Error::Parse(ParseError {
+                position: 0,
+                reason: ParseErrorReason::TooManyDigits(3),
+                ..
+            })
+        ));
+        assert_eq!(
+            render_error.to_string(),
+            "8 needs more than the 3 positions of the table"
+        );
+        assert_eq!(word.render(&BigInt::from(-7)).unwrap(), "-111");
+    }
+
+    #[test]
+    fn test_exact_decomposition() {
+        let weights = [1, 5, 6].map(BigInt::from);
+        let greedy = table(&[1, 5, 6], "012");
+        let exact = TableSystem::new(
+            &System::table(weights, "012").with_decomposition(Decomposition::Smallest),
+        )
+        .unwrap();
+
+        // Greedily 6 leaves 4, which 1 and 5 with digits up to 2 cannot make.
+        assert!(greedy.render(&BigInt::from(10)).is_err());
+        assert_eq!(exact.render(&BigInt::from(10)).unwrap(), "20");
+        assert_eq!(exact.render(&BigInt::from(0)).unwrap(), "0");
+    }
+
+    #[test]
+    fn test_fractions_before_the_origin() {
+        let weights = ["1/4", "1/2", "1", "2", "4"].map(|w| parse_weight_str(w).unwrap());
+        let system = System::table([], "01").with_weights(weights).with_origin(2);
+        let fixed_point = TableSystem::new(&system).unwrap();
+        let seven_quarters = BigRational::new(7.into(), 4.into());
+
+        assert_eq!(fixed_point.parse_rational("1.11").unwrap(), seven_quarters);
+        assert_eq!(
+            fixed_point.render_rational(&seven_quarters).unwrap(),
+            "1.11"
+        );
+        assert_eq!(fixed_point.render(&BigInt::from(6)).unwrap(), "110");
+        assert!(matches!(
+            fixed_point.parse_rational("1.111"),
+            Err(Error::Parse(ParseError {
+                position: 4,
+                reason: ParseErrorReason::TooManyFractionDigits(2),
+                ..
+            }))
+        ));
+        assert!(matches!(
+            TableSystem::new(&system.with_origin(0)),
+            Err(Error::Config(ConfigError::FractionalWeight {
+                position: 0,
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn test_weights_file() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("source.txt");
+        let system = System::table([], "01").with_weights_file(&path);
+        let listing = TableSystem::new(&system).unwrap();
+
+        assert_eq!(listing.render(&BigInt::from(5)).unwrap(), "100000");
+        assert_eq!(listing.parse("101000").unwrap(), BigInt::from(8));
+        assert_eq!(
+            listing.describe(),
+            "weights from a table of 61 positions with digits 01"
+        );
+    }
+
+    #[test]
+    fn test_invalid_weights_file() {
+        let path = std::env::temp_dir().join("numberbases-invalid-weights.txt");
+        fs::write(&path, "# word layout\n1\n\n(16)\nx256\n").unwrap();
+        let system = System::table([], "01").with_weights_file(&path);
+
+        let error = TableSystem::new(&system).unwrap_err();
+
+        assert!(matches!(
+            error,
+            Error::Config(ConfigError::InvalidWeightLine { line: 5, ref text, .. }) if text == "x256"
+        ));
+    }
+}